@@ -1,105 +1,561 @@
 //! Low-level argument parsing.
 
+use crate::Order;
+use std::borrow::Cow;
 use std::ffi::{OsStr, OsString};
 
 /// Trait for string types that can be parsed as command-line arguments.
+///
+/// Parsing is done by [`Syntax`], which examines the bytes of the argument and splits the argument
+/// at character boundaries, so every string type follows exactly the same rules.
 pub trait ArgString: Sized {
-    /// Parse the string as a command-line argument.
+    /// Parse the string as a command-line argument, using the default syntax.
     ///
     /// On failure, return the input.
-    fn parse_arg(self) -> Result<ParsedArg<Self>, Self>;
+    fn parse_arg(self) -> Result<ParsedArg<Self>, Self> {
+        Syntax::new().parse(self)
+    }
 
     /// Convert the argument into a str if it is a valid Unicode string.
     fn to_str(&self) -> Option<&str>;
 
     /// Convert the argument into an OsStr.
     fn to_osstr(&self) -> &OsStr;
+
+    /// Get the bytes of the argument, as examined by the parser.
+    ///
+    /// Any ASCII characters in the argument must appear as the corresponding ASCII bytes, and
+    /// Unicode characters must be encoded as UTF-8.
+    fn arg_bytes(&self) -> Cow<'_, [u8]>;
+
+    /// Get the part of the argument starting at the given offset into [`arg_bytes`].
+    ///
+    /// The parser only calls this with offsets at character boundaries.
+    ///
+    /// [`arg_bytes`]: ArgString::arg_bytes
+    fn arg_tail(&self, start: usize) -> Self;
 }
 
-fn is_arg_name(c: char) -> bool {
-    match c {
-        'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => true,
-        _ => false,
+impl ArgString for String {
+    fn to_str(&self) -> Option<&str> {
+        Some(self)
+    }
+
+    fn to_osstr(&self) -> &OsStr {
+        self.as_ref()
+    }
+
+    fn arg_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.as_bytes())
+    }
+
+    fn arg_tail(&self, start: usize) -> Self {
+        self[start..].to_owned()
     }
 }
 
-impl ArgString for String {
-    fn parse_arg(self) -> Result<ParsedArg<String>, String> {
-        let mut chars = self.chars();
-        match chars.next() {
-            Some('-') => (),
-            _ => return Ok(ParsedArg::Positional(self)),
-        }
-        let cur = chars.clone();
-        match chars.next() {
-            Some('-') => {
-                if chars.as_str().is_empty() {
-                    return Ok(ParsedArg::EndOfFlags);
-                }
+impl ArgString for OsString {
+    fn to_str(&self) -> Option<&str> {
+        OsStr::to_str(self)
+    }
+
+    fn to_osstr(&self) -> &OsStr {
+        self
+    }
+
+    fn arg_bytes(&self) -> Cow<'_, [u8]> {
+        use os_str_bytes::OsStrBytes;
+        OsStr::to_bytes(self)
+    }
+
+    fn arg_tail(&self, start: usize) -> Self {
+        use os_str_bytes::OsStringBytes;
+        OsString::from_bytes(&self.arg_bytes()[start..]).expect("argument split inside a character")
+    }
+}
+
+/// The set of characters which may appear in option names.
+///
+/// Arguments which look like options but whose names contain other characters are rejected as
+/// invalid arguments.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum NameChars {
+    /// ASCII letters, digits, `-`, and `_`. This is the default.
+    #[default]
+    Standard,
+    /// The standard characters, plus `.` and `:`, for names like `--foo.bar` or `--key:sub`.
+    Dotted,
+    /// Any Unicode alphanumeric character, plus `-`, `_`, `.`, and `:`.
+    Unicode,
+    /// Any character accepted by the given function. Unlike the other sets, names may end with
+    /// `-`.
+    Custom(fn(char) -> bool),
+}
+
+impl NameChars {
+    /// Return true if the character may appear in an option name.
+    pub fn accepts(self, c: char) -> bool {
+        match self {
+            NameChars::Standard => matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_'),
+            NameChars::Dotted => {
+                matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' | ':')
             }
-            Some(_) => chars = cur,
-            None => return Ok(ParsedArg::Positional(self)),
+            NameChars::Unicode => c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'),
+            NameChars::Custom(f) => f(c),
         }
-        let body = chars.as_str();
-        let (name, value) = match body.find('=') {
-            Some(idx) => (&body[..idx], Some(&body[idx + 1..])),
-            None => (body, None),
+    }
+}
+
+/// The syntax for options, which decides how each argument is parsed.
+///
+/// The default syntax is the syntax described in the crate documentation: options start with one
+/// or two hyphens, which are equivalent, values are separated from names by `=`, and `--` ends
+/// option processing. Other common conventions are available as built-in profiles, and each part
+/// of the syntax can be changed individually.
+///
+/// ```
+/// use simpleargs::{ArgString, ParsedArg, Syntax};
+/// let syntax = Syntax::windows();
+/// assert_eq!(
+///     syntax.parse("/out:file.txt".to_owned()),
+///     Ok(ParsedArg::Named("out".to_owned(), Some("file.txt".to_owned()))),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Syntax {
+    long: Vec<String>,
+    short: Option<char>,
+    end: Option<String>,
+    names: NameChars,
+    separators: Vec<u8>,
+    attached: Vec<String>,
+    order: Order,
+}
+
+impl Syntax {
+    /// Create the default syntax: `-opt` and `--opt` are equivalent, `-opt=value` has a value,
+    /// and `--` ends option processing.
+    pub fn new() -> Self {
+        Syntax {
+            long: vec!["--".to_owned(), "-".to_owned()],
+            short: None,
+            end: Some("--".to_owned()),
+            names: NameChars::Standard,
+            separators: vec![b'='],
+            attached: Vec::new(),
+            order: Order::Intermixed,
+        }
+    }
+
+    /// Create a syntax like GNU `getopt_long`.
+    ///
+    /// Long options are written `--opt` or `--opt=value`. Short options are single characters
+    /// written `-o`, and can be combined, so `-abc` is the same as `-a -b -c`. A short option
+    /// which takes a value takes the rest of the argument, so `-ofile` is `-o file`. `--` ends
+    /// option processing.
+    pub fn gnu() -> Self {
+        Syntax::new().long_prefixes(&["--"]).short_prefix(Some('-'))
+    }
+
+    /// Create a syntax like POSIX `getopt`.
+    ///
+    /// This is the same as [`gnu`], except that there are no long options, and the first
+    /// positional argument ends option processing, with [`Order::StopAtPositional`].
+    ///
+    /// [`gnu`]: Syntax::gnu
+    pub fn posix() -> Self {
+        Syntax::new()
+            .long_prefixes(&[])
+            .short_prefix(Some('-'))
+            .order(Order::StopAtPositional)
+    }
+
+    /// Create a syntax like the Go `flag` package.
+    ///
+    /// This is the same as the default syntax, except that option names may contain any character
+    /// other than `=` and may not start with `-`, and the first positional argument ends option
+    /// processing, with [`Order::StopAtPositional`].
+    pub fn go() -> Self {
+        Syntax::new()
+            .names(NameChars::Custom(|_| true))
+            .order(Order::StopAtPositional)
+    }
+
+    /// Create a syntax like DOS and Windows tools.
+    ///
+    /// Options are written `/opt` or `/opt:value`, and may be named `/?`. There is no argument
+    /// which ends option processing.
+    pub fn windows() -> Self {
+        Syntax::new()
+            .long_prefixes(&["/"])
+            .end_marker(None)
+            .names(NameChars::Custom(|c| {
+                NameChars::Standard.accepts(c) || c == '?'
+            }))
+            .separators(":")
+    }
+
+    /// Set how positional arguments affect option processing. The default is
+    /// [`Order::Intermixed`]. This can be overridden with [`Args::with_order`].
+    ///
+    /// [`Args::with_order`]: crate::Args::with_order
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Get how positional arguments affect option processing.
+    pub(crate) fn get_order(&self) -> Order {
+        self.order
+    }
+
+    /// Set the prefixes which introduce long options, like `"--"` for `--opt`.
+    ///
+    /// When several prefixes match, the longest is used.
+    pub fn long_prefixes(mut self, prefixes: &[&str]) -> Self {
+        self.long = prefixes.iter().map(|&p| p.to_owned()).collect();
+        self.long.sort_by_key(|p| std::cmp::Reverse(p.len()));
+        self
+    }
+
+    /// Set the character which introduces short options, like `'-'` for `-o`, or `None` to disable
+    /// short options.
+    ///
+    /// Short options are single characters, and several can be combined into one argument. Long
+    /// option prefixes are checked first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix is not an ASCII character.
+    pub fn short_prefix(mut self, prefix: Option<char>) -> Self {
+        if let Some(c) = prefix {
+            assert!(c.is_ascii(), "short option prefix must be ASCII");
+        }
+        self.short = prefix;
+        self
+    }
+
+    /// Set the argument which ends option processing, like `"--"`, or `None` if there is no such
+    /// argument.
+    pub fn end_marker(mut self, marker: Option<&str>) -> Self {
+        self.end = marker.map(str::to_owned);
+        self
+    }
+
+    /// Set the characters accepted in option names.
+    pub fn names(mut self, names: NameChars) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Set the characters which separate a long option name from its value.
+    ///
+    /// The option name ends at the first separator. For example, with `"=:"`, the argument
+    /// `--opt:value` has the name `"opt"` and value `"value"`. If the separators are empty, values
+    /// must be passed as separate arguments (or attached, see [`attached`]).
+    ///
+    /// [`attached`]: Syntax::attached
+    ///
+    /// # Panics
+    ///
+    /// Panics if any separator is not an ASCII character.
+    pub fn separators(mut self, separators: &str) -> Self {
+        assert!(separators.is_ascii(), "option separators must be ASCII");
+        self.separators = separators.as_bytes().to_vec();
+        self
+    }
+
+    /// Set the names of long options which take values attached directly to the name.
+    ///
+    /// If an argument starts with one of these names and is not exactly one of these names, the
+    /// rest of the argument is the value, without looking for a separator. For example, with
+    /// `["D", "o"]`, the argument `-Dname=val` has the name `"D"` and the value `"name=val"`, and
+    /// `-ofile` has the name `"o"` and the value `"file"`. The longest matching name is used. Note
+    /// that this means that `-output` is parsed as `-o` with the value `"utput"`.
+    ///
+    /// If the name is followed by a separator, the value starts after the separator, so `-o=file`
+    /// is the same as `-ofile`. An attached option given by itself, like `-o file`, takes its
+    /// value from the next argument as usual.
+    pub fn attached<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.attached = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Parse a command-line argument.
+    ///
+    /// On failure, return the input.
+    pub fn parse<T: ArgString>(&self, arg: T) -> Result<ParsedArg<T>, T> {
+        self.parse_option(arg).map(|(arg, _)| arg)
+    }
+
+    /// Parse a command-line argument, and also return the option token if it is an option.
+    pub(crate) fn parse_option<T: ArgString>(
+        &self,
+        arg: T,
+    ) -> Result<(ParsedArg<T>, Option<Token<T>>), T> {
+        let bytes = arg.arg_bytes();
+        let token = |name: &str, short: bool| {
+            let spelling = if short {
+                self.short_spelling(name)
+            } else {
+                format!("{}{}", self.long_prefix(&bytes).unwrap_or(""), name)
+            };
+            Some(Token {
+                raw: arg.arg_tail(0),
+                spelling,
+                short,
+                grouped: false,
+            })
         };
-        if name.is_empty() || !name.chars().all(is_arg_name) {
-            return Err(self);
+        Ok(match self.split(&bytes) {
+            None => return Err(arg),
+            Some(Split::Positional) => (ParsedArg::Positional(arg), None),
+            Some(Split::EndOfFlags) => (ParsedArg::EndOfFlags, None),
+            Some(Split::Named(name, value)) => (
+                ParsedArg::Named(name.to_owned(), value.map(|idx| arg.arg_tail(idx))),
+                token(name, false),
+            ),
+            Some(Split::Short(name)) => {
+                (ParsedArg::Named(name.to_owned(), None), token(name, true))
+            }
+            Some(Split::Cluster(name, rest)) => (
+                ParsedArg::Cluster(name.to_owned(), arg.arg_tail(rest)),
+                token(name, true),
+            ),
+        })
+    }
+
+    /// Get a short option as it is written on the command line, like "-x".
+    pub(crate) fn short_spelling(&self, name: &str) -> String {
+        format!("{}{}", self.short.unwrap_or('-'), name)
+    }
+
+    /// Get an option which the user did not type as it should be written, like "--name" or "-n".
+    /// The prefix the user typed for another option is used if it is a long option prefix.
+    pub(crate) fn spelling(&self, name: &str, typed_prefix: &str) -> String {
+        if self.short.is_some() && name.chars().count() == 1 {
+            return self.short_spelling(name);
         }
-        Ok(ParsedArg::Named(name.to_owned(), value.map(str::to_owned)))
+        let prefix = if self.long.iter().any(|p| p == typed_prefix) {
+            typed_prefix
+        } else {
+            match self.long.last() {
+                Some(prefix) => prefix.as_str(),
+                None => return self.short_spelling(name),
+            }
+        };
+        format!("{}{}", prefix, name)
     }
 
-    fn to_str(&self) -> Option<&str> {
-        Some(self)
+    /// Parse the remainder of a group of short options, after the first option has been removed.
+    ///
+    /// Returns [`ParsedArg::Named`] for the last option in the group, or [`ParsedArg::Cluster`] if
+    /// more options follow.
+    pub fn parse_cluster<T: ArgString>(&self, rest: T) -> Result<ParsedArg<T>, T> {
+        let bytes = rest.arg_bytes();
+        Ok(match self.split_short(&bytes, 0) {
+            None => return Err(rest),
+            Some((name, None)) => ParsedArg::Named(name.to_owned(), None),
+            Some((name, Some(idx))) => ParsedArg::Cluster(name.to_owned(), rest.arg_tail(idx)),
+        })
     }
 
-    fn to_osstr(&self) -> &OsStr {
-        self.as_ref()
+    /// Return true if the argument is the marker which ends option processing.
+    pub(crate) fn is_end_marker<T: ArgString>(&self, arg: &T) -> bool {
+        match &self.end {
+            Some(end) => *arg.arg_bytes() == *end.as_bytes(),
+            None => false,
+        }
     }
-}
 
-impl ArgString for OsString {
-    fn parse_arg(self) -> Result<ParsedArg<OsString>, OsString> {
-        use os_str_bytes::{OsStrBytes, OsStringBytes};
-        let bytes = self.to_bytes();
-        if bytes.len() < 2 || bytes[0] != b'-' {
-            return Ok(ParsedArg::Positional(self));
-        }
-        let body = if bytes[1] != b'-' {
-            &bytes[1..]
-        } else if bytes.len() == 2 {
-            return Ok(ParsedArg::EndOfFlags);
+    /// Return true if the argument looks like an option or `--`, rather than a value. Negative
+    /// numbers like `-5` or `-1.5` are not considered options.
+    pub(crate) fn is_option<T: ArgString>(&self, arg: &T) -> bool {
+        let bytes = arg.arg_bytes();
+        if is_negative_number(&bytes) {
+            return false;
+        }
+        !matches!(self.split(&bytes), Some(Split::Positional))
+    }
+
+    /// Split a partially typed option into its parts, for completion. Unlike [`Syntax::parse`],
+    /// the name may be empty or incomplete. Returns `None` if the argument is not an option.
+    pub(crate) fn split_partial<'a>(&self, arg: &'a str) -> Option<Partial<'a>> {
+        let long = self.long.iter().find(|p| arg.starts_with(p.as_str()));
+        let (prefix, short) = match (long, self.short) {
+            (Some(prefix), _) => (&arg[..prefix.len()], false),
+            (None, Some(c)) if arg.starts_with(c) => (&arg[..c.len_utf8()], true),
+            _ => return None,
+        };
+        let body = &arg[prefix.len()..];
+        let (name, value) =
+            match body.find(|c: char| c.is_ascii() && self.separators.contains(&(c as u8))) {
+                Some(idx) if !short => (&body[..idx], Some(&body[idx + 1..])),
+                _ => (body, None),
+            };
+        Some(Partial {
+            prefix,
+            short,
+            name,
+            value,
+        })
+    }
+
+    /// Return true if the string is a valid option name.
+    fn is_name(&self, name: &str) -> bool {
+        !name.is_empty()
+            && !name.starts_with('-')
+            && (matches!(self.names, NameChars::Custom(_)) || !name.ends_with('-'))
+            && name.chars().all(|c| self.names.accepts(c))
+    }
+
+    /// Split an argument into its parts.
+    ///
+    /// This is the argument grammar shared by all [`ArgString`] implementations, so that every
+    /// string type accepts and rejects exactly the same arguments. Returns `None` if the argument
+    /// looks like an option but is not valid.
+    fn split<'a>(&'a self, arg: &'a [u8]) -> Option<Split<'a>> {
+        if let Some(end) = &self.end {
+            if arg == end.as_bytes() {
+                return Some(Split::EndOfFlags);
+            }
+        }
+        if let Some(prefix) = self.long_prefix(arg) {
+            return self.split_long(arg, prefix.len());
+        }
+        match self.short {
+            Some(c) if arg.len() > 1 && arg[0] == c as u8 => match self.split_short(arg, 1)? {
+                (name, None) => Some(Split::Short(name)),
+                (name, Some(rest)) => Some(Split::Cluster(name, rest)),
+            },
+            _ => Some(Split::Positional),
+        }
+    }
+
+    /// Get the long option prefix which an argument starts with, if any.
+    fn long_prefix(&self, arg: &[u8]) -> Option<&str> {
+        self.long
+            .iter()
+            .find(|p| arg.len() > p.len() && arg.starts_with(p.as_bytes()))
+            .map(String::as_str)
+    }
+
+    /// Split a long option, which starts at the given offset.
+    fn split_long<'a>(&'a self, arg: &'a [u8], start: usize) -> Option<Split<'a>> {
+        let body = &arg[start..];
+        // An attached option given by itself takes its value from the next argument.
+        let attached = if self.attached.iter().any(|name| body == name.as_bytes()) {
+            None
         } else {
-            &bytes[2..]
+            self.attached
+                .iter()
+                .filter(|name| body.starts_with(name.as_bytes()))
+                .max_by_key(|name| name.len())
         };
-        let (name, value) = match body.iter().position(|&c| c == b'=') {
+        if let Some(name) = attached {
+            if !self.is_name(name) {
+                return None;
+            }
+            // A separator right after the name is not part of the value.
+            let mut value = start + name.len();
+            if matches!(body.get(name.len()), Some(c) if self.separators.contains(c)) {
+                value += 1;
+            }
+            return Some(Split::Named(name, Some(value)));
+        }
+        let (name, value) = match body.iter().position(|c| self.separators.contains(c)) {
             None => (body, None),
-            Some(idx) => (&body[..idx], Some(&body[idx + 1..])),
+            Some(idx) => (&body[..idx], Some(start + idx + 1)),
         };
-        if name.len() == 0
-            || name[0] == b'-'
-            || name[name.len() - 1] == b'-'
-            || !name.iter().all(|&c| is_arg_name(c as char))
-        {
-            return Err(self);
+        let name = std::str::from_utf8(name).ok()?;
+        if !self.is_name(name) {
+            return None;
         }
-        let name = Vec::from(name);
-        let name = unsafe { String::from_utf8_unchecked(name) };
-        let value = value.map(|v| unsafe { OsString::from_bytes_unchecked(v) });
-        Ok(ParsedArg::Named(name, value))
+        Some(Split::Named(name, value))
     }
 
-    fn to_str(&self) -> Option<&str> {
-        OsStr::to_str(self)
+    /// Split a short option, which starts at the given offset. Returns the option name and the
+    /// offset of the rest of the argument, if anything follows the option.
+    fn split_short<'a>(&self, arg: &'a [u8], start: usize) -> Option<(&'a str, Option<usize>)> {
+        let body = &arg[start..];
+        let valid = match std::str::from_utf8(body) {
+            Ok(s) => s,
+            Err(e) => std::str::from_utf8(&body[..e.valid_up_to()]).unwrap(),
+        };
+        let len = valid.chars().next()?.len_utf8();
+        let name = &valid[..len];
+        if !self.is_name(name) {
+            return None;
+        }
+        if body.len() > len {
+            Some((name, Some(start + len)))
+        } else {
+            Some((name, None))
+        }
     }
+}
 
-    fn to_osstr(&self) -> &OsStr {
-        self
+impl Default for Syntax {
+    fn default() -> Self {
+        Syntax::new()
     }
 }
 
+/// Return true if the argument is a negative number.
+fn is_negative_number(arg: &[u8]) -> bool {
+    match arg.split_first() {
+        Some((b'-', rest)) => match rest.first() {
+            Some(c) if c.is_ascii_digit() || *c == b'.' => std::str::from_utf8(rest)
+                .ok()
+                .and_then(|s| s.parse::<f64>().ok())
+                .is_some(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// The parts of an argument, as found by [`Syntax::split`].
+enum Split<'a> {
+    Positional,
+    EndOfFlags,
+    /// An option name, and the offset of its value within the argument, if it has one.
+    Named(&'a str, Option<usize>),
+    /// A short option name, with nothing following it.
+    Short(&'a str),
+    /// A short option name, and the offset of the rest of the argument.
+    Cluster(&'a str, usize),
+}
+
+/// A partially typed option, as found by [`Syntax::split_partial`].
+pub(crate) struct Partial<'a> {
+    /// The option prefix, like "--".
+    pub prefix: &'a str,
+    /// True if the prefix is for short options.
+    pub short: bool,
+    /// The option name typed so far.
+    pub name: &'a str,
+    /// The value typed so far, after a separator.
+    pub value: Option<&'a str>,
+}
+
+/// An option as it appeared on the command line.
+pub(crate) struct Token<T> {
+    /// The full argument containing the option.
+    pub raw: T,
+    /// The option prefix and name, like "--name", without any value.
+    pub spelling: String,
+    /// True for short options.
+    pub short: bool,
+    /// True for a short option which follows another option in the same group, like "b" in
+    /// "-abc".
+    pub grouped: bool,
+}
+
 /// A single command-line argument which has been parsed.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParsedArg<T> {
@@ -111,6 +567,12 @@ pub enum ParsedArg<T> {
     ///
     /// The leading dashes are removed from the name.
     Named(String, Option<T>),
+    /// A short option followed by more text in the same argument, such as "-abc", when short
+    /// options are enabled.
+    ///
+    /// The rest of the argument is either the option's value or more short options, which can be
+    /// parsed with [`Syntax::parse_cluster`].
+    Cluster(String, T),
 }
 
 impl<T> ParsedArg<T> {
@@ -123,6 +585,7 @@ impl<T> ParsedArg<T> {
             ParsedArg::Positional(x) => ParsedArg::Positional(f(x)),
             ParsedArg::EndOfFlags => ParsedArg::EndOfFlags,
             ParsedArg::Named(x, y) => ParsedArg::Named(x, y.map(f)),
+            ParsedArg::Cluster(x, y) => ParsedArg::Cluster(x, f(y)),
         }
     }
 }
@@ -218,8 +681,89 @@ mod test {
         }
     }
 
-    const FAIL_CASES: &'static [&'static str] =
-        &["-\0", "--\n", "--\0=", "-=", "--=", "-=value", "--=xyz"];
+    const FAIL_CASES: &[&str] = &["-\0", "--\n", "--\0=", "-=", "--=", "-=value", "--=xyz"];
+
+    #[test]
+    fn parse_name_chars() {
+        let cases: &[(&str, NameChars, Option<&str>)] = &[
+            ("--foo.bar=1", NameChars::Standard, None),
+            ("--foo.bar=1", NameChars::Dotted, Some("foo.bar")),
+            ("--key:sub", NameChars::Dotted, Some("key:sub")),
+            ("--größe=3", NameChars::Dotted, None),
+            ("--größe=3", NameChars::Unicode, Some("größe")),
+            ("-a+b", NameChars::Custom(|c| c != '='), Some("a+b")),
+        ];
+        let mut success = true;
+        for &(input, names, expected) in cases.iter() {
+            let expected = expected.map(str::to_owned);
+            let syntax = Syntax::new().names(names);
+            let got_str = match syntax.parse(input.to_owned()) {
+                Ok(ParsedArg::Named(name, _)) => Some(name),
+                _ => None,
+            };
+            let got_os = match syntax.parse(OsString::from(input)) {
+                Ok(ParsedArg::Named(name, _)) => Some(name),
+                _ => None,
+            };
+            if got_str != expected || got_os != expected {
+                eprintln!(
+                    "{:?} with {:?}: got {:?} and {:?}, expect {:?}",
+                    input, names, got_str, got_os, expected
+                );
+                success = false;
+            }
+        }
+        if !success {
+            panic!("failed");
+        }
+    }
+
+    type SyntaxCase<'a> = (&'a str, &'a Syntax, Option<(&'a str, Option<&'a str>)>);
+
+    #[test]
+    fn parse_values() {
+        let attached = Syntax::new().attached(vec!["D", "o", "out"]);
+        let separated = Syntax::new().separators("=:");
+        let cases: &[SyntaxCase] = &[
+            ("-Dname=val", &attached, Some(("D", Some("name=val")))),
+            ("-D", &attached, Some(("D", None))),
+            ("-ofile", &attached, Some(("o", Some("file")))),
+            ("--o=file", &attached, Some(("o", Some("file")))),
+            ("-o==x", &attached, Some(("o", Some("=x")))),
+            ("-outfile", &attached, Some(("out", Some("file")))),
+            ("-out", &attached, Some(("out", None))),
+            ("-x=y", &attached, Some(("x", Some("y")))),
+            ("--opt:value", &separated, Some(("opt", Some("value")))),
+            ("--opt=a:b", &separated, Some(("opt", Some("a:b")))),
+            ("--x:", &separated, Some(("x", Some("")))),
+            ("-:x", &separated, None),
+        ];
+        let mut success = true;
+        for &(input, syntax, expected) in cases.iter() {
+            let expected =
+                expected.map(|(name, value)| (name.to_owned(), value.map(str::to_owned)));
+            let got_str = match syntax.parse(input.to_owned()) {
+                Ok(ParsedArg::Named(name, value)) => Some((name, value)),
+                _ => None,
+            };
+            let got_os = match syntax.parse(OsString::from(input)) {
+                Ok(ParsedArg::Named(name, value)) => {
+                    Some((name, value.map(|v| v.into_string().unwrap())))
+                }
+                _ => None,
+            };
+            if got_str != expected || got_os != expected {
+                eprintln!(
+                    "{:?}: got {:?} and {:?}, expect {:?}",
+                    input, got_str, got_os, expected
+                );
+                success = false;
+            }
+        }
+        if !success {
+            panic!("failed");
+        }
+    }
 
     #[test]
     fn parse_string_success() {
@@ -288,4 +832,166 @@ mod test {
             panic!("failed");
         }
     }
+
+    /// Expected result of parsing an argument, in a form which does not depend on string type.
+    enum Expect {
+        Positional,
+        EndOfFlags,
+        Named(&'static str, Option<&'static [u8]>),
+        Invalid,
+    }
+
+    /// Cases which every [`ArgString`] implementation must agree on. Inputs which a string type
+    /// cannot represent, like invalid UTF-8 for [`String`], are skipped for that type.
+    const CONFORMANCE: &[(&[u8], Expect)] = &[
+        (b"", Expect::Positional),
+        (b"abc", Expect::Positional),
+        (b"-", Expect::Positional),
+        (b"a-b", Expect::Positional),
+        (b"--", Expect::EndOfFlags),
+        (b"---", Expect::Invalid),
+        (b"-x", Expect::Named("x", None)),
+        (b"--x", Expect::Named("x", None)),
+        (b"---x", Expect::Invalid),
+        (b"-x-", Expect::Invalid),
+        (b"--x-", Expect::Invalid),
+        (b"--x-y", Expect::Named("x-y", None)),
+        (b"--x_", Expect::Named("x_", None)),
+        (b"-1", Expect::Named("1", None)),
+        (b"-x=", Expect::Named("x", Some(b""))),
+        (b"--x=a=b", Expect::Named("x", Some(b"a=b"))),
+        (b"--x=-y", Expect::Named("x", Some(b"-y"))),
+        (b"---x=y", Expect::Invalid),
+        (b"-=", Expect::Invalid),
+        (b"--=x", Expect::Invalid),
+        (b"-x y", Expect::Invalid),
+        (b"--\xc3\xa9", Expect::Invalid),
+        (b"--x=\xc3\xa9", Expect::Named("x", Some(b"\xc3\xa9"))),
+        (b"\xff", Expect::Positional),
+        (b"--\xff", Expect::Invalid),
+        (b"--x\xff=y", Expect::Invalid),
+        (b"--x=\xff", Expect::Named("x", Some(b"\xff"))),
+    ];
+
+    /// A string type which can be checked against the conformance cases.
+    trait TestArg: ArgString + Debug {
+        fn from_bytes(bytes: &[u8]) -> Option<Self>;
+        fn to_bytes(&self) -> Vec<u8>;
+    }
+
+    impl TestArg for String {
+        fn from_bytes(bytes: &[u8]) -> Option<Self> {
+            String::from_utf8(bytes.to_vec()).ok()
+        }
+
+        fn to_bytes(&self) -> Vec<u8> {
+            self.as_bytes().to_vec()
+        }
+    }
+
+    impl TestArg for OsString {
+        fn from_bytes(bytes: &[u8]) -> Option<Self> {
+            Some(osstr(bytes))
+        }
+
+        fn to_bytes(&self) -> Vec<u8> {
+            self.as_bytes().to_vec()
+        }
+    }
+
+    /// Parse an argument with a string type, converting the result to bytes so it can be compared
+    /// across types. Returns None if the type cannot represent the input.
+    fn conform<T: TestArg>(input: &[u8]) -> Option<Result<ParsedArg<Vec<u8>>, Vec<u8>>> {
+        let arg = T::from_bytes(input)?;
+        Some(match arg.parse_arg() {
+            Ok(arg) => Ok(arg.map(|x| x.to_bytes())),
+            Err(arg) => Err(arg.to_bytes()),
+        })
+    }
+
+    impl Expect {
+        fn result(&self, input: &[u8]) -> Result<ParsedArg<Vec<u8>>, Vec<u8>> {
+            match *self {
+                Expect::Positional => Ok(ParsedArg::Positional(input.to_vec())),
+                Expect::EndOfFlags => Ok(ParsedArg::EndOfFlags),
+                Expect::Named(name, value) => {
+                    Ok(ParsedArg::Named(name.to_owned(), value.map(<[u8]>::to_vec)))
+                }
+                Expect::Invalid => Err(input.to_vec()),
+            }
+        }
+    }
+
+    fn check_conformance<T: TestArg>() -> bool {
+        let mut success = true;
+        for (input, expect) in CONFORMANCE.iter() {
+            let got = match conform::<T>(input) {
+                None => continue,
+                Some(r) => r,
+            };
+            let expect = expect.result(input);
+            if got != expect {
+                eprintln!(
+                    "{}: {:?}.parse_arg(): got {:?}, expect {:?}",
+                    std::any::type_name::<T>(),
+                    OsStr::from_bytes(input),
+                    got,
+                    expect
+                );
+                success = false;
+            }
+        }
+        success
+    }
+
+    /// Define a conformance test for each string type, and a test that all types agree with each
+    /// other on every input they can all represent. New ArgString implementations should be added
+    /// to the list.
+    macro_rules! conformance_tests {
+        ($($name:ident: $type:ty,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    if !check_conformance::<$type>() {
+                        panic!("failed");
+                    }
+                }
+            )*
+
+            #[test]
+            fn conformance_agree() {
+                let mut success = true;
+                for (input, _) in CONFORMANCE.iter() {
+                    let results = vec![$(
+                        (stringify!($type), conform::<$type>(input)),
+                    )*];
+                    let results: Vec<_> = results
+                        .into_iter()
+                        .filter_map(|(ty, r)| r.map(|r| (ty, r)))
+                        .collect();
+                    for (ty, r) in results.iter().skip(1) {
+                        if r != &results[0].1 {
+                            eprintln!(
+                                "{:?}: {} gives {:?}, {} gives {:?}",
+                                OsStr::from_bytes(input),
+                                results[0].0,
+                                results[0].1,
+                                ty,
+                                r
+                            );
+                            success = false;
+                        }
+                    }
+                }
+                if !success {
+                    panic!("failed");
+                }
+            }
+        };
+    }
+
+    conformance_tests! {
+        conformance_string: String,
+        conformance_osstring: OsString,
+    }
 }
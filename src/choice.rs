@@ -0,0 +1,141 @@
+//! Option values chosen from a fixed list.
+
+use crate::OptionError;
+
+/// A list of names which an option value can be chosen from, and the value for each name.
+///
+/// ```
+/// use simpleargs::Choices;
+/// let choices = Choices::new(&[("json", 1), ("yaml", 2), ("text", 3)]).ignore_case();
+/// assert_eq!(choices.find("YAML").ok(), Some(&2));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Choices<'c, C> {
+    choices: &'c [(&'c str, C)],
+    ignore_case: bool,
+    prefix: bool,
+}
+
+impl<'c, C> Choices<'c, C> {
+    /// Create a list of choices. Names are listed in error messages in the same order.
+    pub fn new(choices: &'c [(&'c str, C)]) -> Self {
+        Choices {
+            choices,
+            ignore_case: false,
+            prefix: false,
+        }
+    }
+
+    /// Match names without regard to case.
+    pub fn ignore_case(mut self) -> Self {
+        self.ignore_case = true;
+        self
+    }
+
+    /// Accept any prefix of a name which matches only one name, so `"j"` can be used for
+    /// `"json"`. An exact match is always accepted.
+    pub fn prefix(mut self) -> Self {
+        self.prefix = true;
+        self
+    }
+
+    /// Get the names which can be chosen.
+    pub fn names(&self) -> impl Iterator<Item = &'c str> {
+        self.choices.iter().map(|&(name, _)| name)
+    }
+
+    /// Find the value for a name.
+    ///
+    /// Returns [`OptionError::InvalidChoice`] if the name does not match exactly one choice.
+    pub fn find(&self, value: &str) -> Result<&'c C, OptionError> {
+        let fold = |s: &str| {
+            if self.ignore_case {
+                s.to_lowercase()
+            } else {
+                s.to_owned()
+            }
+        };
+        let value = fold(value);
+        if let Some((_, choice)) = self.choices.iter().find(|(name, _)| fold(name) == value) {
+            return Ok(choice);
+        }
+        let mut found = None;
+        if self.prefix {
+            for (name, choice) in self.choices.iter() {
+                if fold(name).starts_with(&value) {
+                    found = match found {
+                        None => Some(choice),
+                        Some(_) => return Err(self.error()),
+                    };
+                }
+            }
+        }
+        found.ok_or_else(|| self.error())
+    }
+
+    fn error(&self) -> OptionError {
+        OptionError::InvalidChoice(self.names().map(str::to_owned).collect())
+    }
+}
+
+/// A type whose values can be chosen by name on the command line, usually an enum.
+///
+/// ```
+/// use simpleargs::{Choice, Choices};
+/// #[derive(Clone, Copy)]
+/// enum Format {
+///     Json,
+///     Yaml,
+///     Text,
+/// }
+/// impl Choice for Format {
+///     fn choices() -> Choices<'static, Format> {
+///         Choices::new(&[
+///             ("json", Format::Json),
+///             ("yaml", Format::Yaml),
+///             ("text", Format::Text),
+///         ])
+///     }
+/// }
+/// ```
+pub trait Choice: Sized + 'static {
+    /// Get the names which can be chosen, and the value for each name.
+    fn choices() -> Choices<'static, Self>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FORMATS: &[(&str, i32)] = &[("json", 1), ("yaml", 2), ("text", 3), ("toml", 4)];
+
+    #[test]
+    fn find() {
+        let exact = Choices::new(FORMATS);
+        let loose = Choices::new(FORMATS).ignore_case().prefix();
+        let cases: &[(&str, Option<i32>, Option<i32>)] = &[
+            ("json", Some(1), Some(1)),
+            ("JSON", None, Some(1)),
+            ("y", None, Some(2)),
+            ("Te", None, Some(3)),
+            ("t", None, None),
+            ("", None, None),
+            ("xml", None, None),
+        ];
+        for &(input, expect_exact, expect_loose) in cases.iter() {
+            assert_eq!(exact.find(input).ok().cloned(), expect_exact, "{:?}", input);
+            assert_eq!(loose.find(input).ok().cloned(), expect_loose, "{:?}", input);
+        }
+        match exact.find("xml") {
+            Err(OptionError::InvalidChoice(names)) => {
+                assert_eq!(names, vec!["json", "yaml", "text", "toml"])
+            }
+            r => panic!("incorrect result: {:?}", r),
+        }
+        // An exact match wins over earlier prefix matches.
+        let overlap = Choices::new(&[("ab", 1), ("abc", 2), ("a", 3)]).prefix();
+        assert_eq!(overlap.find("a").ok(), Some(&3));
+        assert_eq!(overlap.find("abc").ok(), Some(&2));
+        assert!(overlap.find("").is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Constraints between named options.
+
+use crate::{Syntax, UsageError};
+
+/// An option which was used on the command line.
+#[derive(Debug, Clone)]
+pub(crate) struct Seen {
+    /// The name passed to the parsing function.
+    pub name: String,
+    /// The option as the user typed it, like "--name".
+    pub option: String,
+    /// The prefix the user typed before the name, like "--".
+    pub prefix: String,
+}
+
+#[derive(Debug, Clone)]
+enum Rule {
+    Required(String),
+    Conflicts(String, String),
+    Requires(String, String),
+    ExactlyOne(Vec<String>),
+}
+
+/// Rules for which options must be used, and which options can be used together.
+///
+/// Option names are the names passed to the [`NamedArgument::parse`] callback, without leading
+/// dashes. The rules are checked after parsing with [`Args::check`], which knows which options
+/// were used. Errors refer to options as the user typed them.
+///
+/// [`NamedArgument::parse`]: crate::NamedArgument::parse
+/// [`Args::check`]: crate::Args::check
+///
+/// ```
+/// use simpleargs::Constraints;
+/// let constraints = Constraints::new()
+///     .required("target")
+///     .conflicts("quiet", "verbose")
+///     .requires("key", "cert")
+///     .exactly_one_of(vec!["build", "test"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Constraints {
+    rules: Vec<Rule>,
+}
+
+impl Constraints {
+    /// Create an empty set of constraints.
+    pub fn new() -> Self {
+        Constraints { rules: Vec::new() }
+    }
+
+    /// Require that an option is used.
+    pub fn required(mut self, name: &str) -> Self {
+        self.rules.push(Rule::Required(name.to_owned()));
+        self
+    }
+
+    /// Require that two options are not used together.
+    pub fn conflicts(mut self, name: &str, other: &str) -> Self {
+        self.rules
+            .push(Rule::Conflicts(name.to_owned(), other.to_owned()));
+        self
+    }
+
+    /// Require that if an option is used, another option is also used.
+    pub fn requires(mut self, name: &str, required: &str) -> Self {
+        self.rules
+            .push(Rule::Requires(name.to_owned(), required.to_owned()));
+        self
+    }
+
+    /// Require that exactly one of a group of options is used.
+    pub fn exactly_one_of<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rules.push(Rule::ExactlyOne(
+            names.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Check the constraints against the options which were used, in the order they were used.
+    /// Returns the first violated constraint, in the order the constraints were added.
+    pub(crate) fn check<T>(&self, seen: &[Seen], syntax: &Syntax) -> Result<(), UsageError<T>> {
+        let find = |name: &str| seen.iter().find(|s| s.name == name);
+        for rule in self.rules.iter() {
+            match rule {
+                Rule::Required(name) => {
+                    if find(name).is_none() {
+                        return Err(UsageError::MissingOption {
+                            option: syntax.spelling(name, ""),
+                        });
+                    }
+                }
+                Rule::Conflicts(a, b) => {
+                    let a = seen.iter().position(|s| &s.name == a);
+                    let b = seen.iter().position(|s| &s.name == b);
+                    if let (Some(a), Some(b)) = (a, b) {
+                        return Err(conflict(&seen[a.min(b)], &seen[a.max(b)]));
+                    }
+                }
+                Rule::Requires(name, required) => {
+                    if let (Some(s), None) = (find(name), find(required)) {
+                        return Err(UsageError::OptionRequires {
+                            name: s.option.clone(),
+                            required: syntax.spelling(required, &s.prefix),
+                        });
+                    }
+                }
+                Rule::ExactlyOne(names) => {
+                    let mut used = seen.iter().filter(|s| names.contains(&s.name));
+                    let first = match used.next() {
+                        Some(first) => first,
+                        None => {
+                            return Err(UsageError::MissingOneOf {
+                                options: names.iter().map(|n| syntax.spelling(n, "")).collect(),
+                            })
+                        }
+                    };
+                    if let Some(second) = used.find(|s| s.name != first.name) {
+                        return Err(conflict(first, second));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn conflict<T>(first: &Seen, second: &Seen) -> UsageError<T> {
+    UsageError::ConflictingOptions {
+        first: first.option.clone(),
+        second: second.option.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn seen(names: &[&str]) -> Vec<Seen> {
+        names
+            .iter()
+            .map(|&option| {
+                let name = option.trim_start_matches('-');
+                Seen {
+                    name: name.to_owned(),
+                    option: option.to_owned(),
+                    prefix: option[..option.len() - name.len()].to_owned(),
+                }
+            })
+            .collect()
+    }
+
+    fn check(constraints: &Constraints, names: &[&str]) -> Result<(), String> {
+        check_syntax(constraints, &Syntax::new(), names)
+    }
+
+    fn check_syntax(
+        constraints: &Constraints,
+        syntax: &Syntax,
+        names: &[&str],
+    ) -> Result<(), String> {
+        constraints
+            .check::<String>(&seen(names), syntax)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn constraints() {
+        let c = Constraints::new().required("target");
+        assert_eq!(check(&c, &["--target"]), Ok(()));
+        assert_eq!(
+            check(&c, &["x"]),
+            Err("missing required option -target".to_owned())
+        );
+        let c = Constraints::new()
+            .conflicts("quiet", "verbose")
+            .requires("key", "cert");
+        assert_eq!(check(&c, &[]), Ok(()));
+        assert_eq!(check(&c, &["quiet", "key", "cert"]), Ok(()));
+        assert_eq!(
+            check(&c, &["-verbose", "--quiet"]),
+            Err("option --quiet cannot be used with -verbose".to_owned())
+        );
+        assert_eq!(
+            check(&c, &["--key"]),
+            Err("option --key requires --cert".to_owned())
+        );
+        let c = Constraints::new().exactly_one_of(vec!["a", "b", "c"]);
+        assert_eq!(check(&c, &["b", "b"]), Ok(()));
+        assert_eq!(
+            check(&c, &["x"]),
+            Err("one of the options -a, -b, -c is required".to_owned())
+        );
+        assert_eq!(
+            check(&c, &["-c", "-x", "-a"]),
+            Err("option -a cannot be used with -c".to_owned())
+        );
+    }
+
+    #[test]
+    fn spelling() {
+        let c = Constraints::new().required("target");
+        assert_eq!(
+            check_syntax(&c, &Syntax::gnu(), &[]),
+            Err("missing required option --target".to_owned())
+        );
+        assert_eq!(
+            check_syntax(&c, &Syntax::windows(), &[]),
+            Err("missing required option /target".to_owned())
+        );
+        let c = Constraints::new().exactly_one_of(vec!["all", "b"]);
+        assert_eq!(
+            check_syntax(&c, &Syntax::gnu(), &[]),
+            Err("one of the options --all, -b is required".to_owned())
+        );
+        assert_eq!(
+            check_syntax(&c, &Syntax::windows(), &[]),
+            Err("one of the options /all, /b is required".to_owned())
+        );
+    }
+}
@@ -1,8 +1,15 @@
+use crate::message::{Catalog, English, Localized, Message};
+use crate::ArgString;
 use std::error::Error;
+use std::ffi::OsString;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::sync::Arc;
 
 /// An error for an invalid named argument.
-#[derive(Debug)]
+///
+/// Errors can be compared for testing. Inner errors in [`OptionError::InvalidValue`] are compared
+/// by their messages.
+#[derive(Debug, Clone)]
 pub enum OptionError {
     /// The named argument is unrecognized.
     ///
@@ -15,32 +22,98 @@ pub enum OptionError {
     /// with no parameter.
     MissingParameter,
 
+    /// The named argument requires a parameter, but the next argument looks like an option.
+    ///
+    /// For example, the program accepts `--output <file>`, but the user passed `--output
+    /// --verbose`. Contains the next argument, which is not consumed. This is only reported if
+    /// enabled with [`ValuePolicy::RejectOptions`].
+    ///
+    /// [`ValuePolicy::RejectOptions`]: crate::ValuePolicy::RejectOptions
+    OptionAsParameter(String),
+
     /// The named argument does not accept a parameter, but one was supplied.
     ///
     /// For example, the program accepts `--verbose`, but an argument was passed as `--verbose=3`.
     UnexpectedParameter,
 
+    /// The named argument is an abbreviation which matches more than one option.
+    ///
+    /// For example, the program accepts `--verbose` and `--version`, but the user passed `--ver`.
+    /// Contains the names of the matching options.
+    Ambiguous(Vec<String>),
+
     /// The named argument was passed a value which is not valid unicode.
     InvalidUnicode,
 
+    /// The value for the named argument was not one of the accepted choices.
+    ///
+    /// For example, the program accepts `--format=<json|yaml|text>`, but the user passed
+    /// `--format=xml`. Contains the accepted choices.
+    InvalidChoice(Vec<String>),
+
     /// The value for the named argument was invalid.
     ///
     /// For example, the program accepts `--jobs=<N>` with integer N, but the user passed in
-    /// `--jobs=xyz`.
-    InvalidValue(Box<dyn Error>),
+    /// `--jobs=xyz`. If the option has no value, this is reported as a failure of the option
+    /// itself.
+    InvalidValue(Arc<dyn Error + Send + Sync>),
+}
+
+impl OptionError {
+    /// Create an error with a custom message, without defining a new error type.
+    ///
+    /// ```
+    /// use simpleargs::OptionError;
+    /// let err = OptionError::custom("must be a power of two");
+    /// ```
+    pub fn custom<S>(message: S) -> Self
+    where
+        S: Into<String>,
+    {
+        let err: Box<dyn Error + Send + Sync> = message.into().into();
+        OptionError::InvalidValue(err.into())
+    }
+}
+
+impl PartialEq for OptionError {
+    fn eq(&self, other: &Self) -> bool {
+        use OptionError::*;
+        match (self, other) {
+            (Unknown, Unknown)
+            | (MissingParameter, MissingParameter)
+            | (UnexpectedParameter, UnexpectedParameter)
+            | (InvalidUnicode, InvalidUnicode) => true,
+            (OptionAsParameter(a), OptionAsParameter(b)) => a == b,
+            (Ambiguous(a), Ambiguous(b)) | (InvalidChoice(a), InvalidChoice(b)) => a == b,
+            (InvalidValue(a), InvalidValue(b)) => a.to_string() == b.to_string(),
+            _ => false,
+        }
+    }
 }
 
 impl<T> From<T> for OptionError
 where
-    T: Error + 'static,
+    T: Error + Send + Sync + 'static,
 {
     fn from(x: T) -> OptionError {
-        OptionError::InvalidValue(Box::new(x))
+        OptionError::InvalidValue(Arc::new(x))
     }
 }
 
-/// A command-line usage error, for when the user has passed incorrect arguments to the program.
+/// The error for [`OptionError::InvalidUnicode`], for options without a value to show.
 #[derive(Debug)]
+struct InvalidUnicode;
+
+impl Display for InvalidUnicode {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str("invalid Unicode string")
+    }
+}
+
+impl Error for InvalidUnicode {}
+
+/// A command-line usage error, for when the user has passed incorrect arguments to the program.
+#[derive(Debug, Clone, PartialEq)]
 pub enum UsageError<T> {
     /// Indicates an argument has invalid syntax. Used for arguments which cannot be parsed.
     InvalidArgument {
@@ -64,11 +137,222 @@ pub enum UsageError<T> {
     InvalidOption {
         /// The name of the option without any leading dashes.
         name: String,
+        /// The option as the user typed it, like `--name`, without any value.
+        option: String,
         /// The option parameter value, if it exists.
         value: Option<T>,
         /// The inner error from parsing the option.
         err: OptionError,
     },
+
+    /// Indicates a required option was not used.
+    MissingOption {
+        /// The option, written with a prefix which is valid for it, like `--name`.
+        option: String,
+    },
+
+    /// Indicates two options were used which cannot be used together.
+    ConflictingOptions {
+        /// The option which appeared first, as typed by the user, like `--name`.
+        first: String,
+        /// The option which appeared second, as typed by the user.
+        second: String,
+    },
+
+    /// Indicates an option was used without another option it requires.
+    OptionRequires {
+        /// The option which was used, as typed by the user, like `--name`.
+        name: String,
+        /// The option which is required, written with a prefix which is valid for it, like
+        /// `--name`.
+        required: String,
+    },
+
+    /// Indicates none of a group of options was used, when exactly one is required.
+    MissingOneOf {
+        /// The options in the group, written with a prefix which is valid for each, like
+        /// `--name`.
+        options: Vec<String>,
+    },
+
+    /// Multiple errors, collected with [`UsageErrors`]. Contains at least two errors, none of
+    /// which are `Multiple`.
+    Multiple(Vec<UsageError<T>>),
+}
+
+impl<T> UsageError<T> {
+    /// Map a `UsageError<T>` to a `UsageError<U>` by applying a function to the argument values.
+    pub fn map<U, F>(self, mut f: F) -> UsageError<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        self.map_mut(&mut f)
+    }
+
+    fn map_mut<U, F>(self, f: &mut F) -> UsageError<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        match self {
+            UsageError::InvalidArgument { arg } => UsageError::InvalidArgument { arg: f(arg) },
+            UsageError::UnexpectedArgument { arg } => {
+                UsageError::UnexpectedArgument { arg: f(arg) }
+            }
+            UsageError::MissingArgument { name } => UsageError::MissingArgument { name },
+            UsageError::InvalidOption {
+                name,
+                option,
+                value,
+                err,
+            } => UsageError::InvalidOption {
+                name,
+                option,
+                value: value.map(f),
+                err,
+            },
+            UsageError::MissingOption { option } => UsageError::MissingOption { option },
+            UsageError::ConflictingOptions { first, second } => {
+                UsageError::ConflictingOptions { first, second }
+            }
+            UsageError::OptionRequires { name, required } => {
+                UsageError::OptionRequires { name, required }
+            }
+            UsageError::MissingOneOf { options } => UsageError::MissingOneOf { options },
+            UsageError::Multiple(errors) => {
+                UsageError::Multiple(errors.into_iter().map(|e| e.map_mut(f)).collect())
+            }
+        }
+    }
+}
+
+impl<T> UsageError<T>
+where
+    T: ArgString,
+{
+    /// Convert the argument values to strings, replacing invalid Unicode with U+FFFD.
+    pub fn lossy(self) -> UsageError<String> {
+        self.map(|x| x.to_osstr().to_string_lossy().into_owned())
+    }
+}
+
+impl From<UsageError<OsString>> for UsageError<String> {
+    fn from(err: UsageError<OsString>) -> Self {
+        err.lossy()
+    }
+}
+
+impl<T> UsageError<T>
+where
+    T: Debug,
+{
+    /// Get the message for this error, or `None` for [`UsageError::Multiple`].
+    pub fn message(&self) -> Option<Message<'_>> {
+        Some(match self {
+            UsageError::InvalidArgument { arg } => Message::InvalidArgument { arg },
+            UsageError::UnexpectedArgument { arg } => Message::UnexpectedArgument { arg },
+            UsageError::MissingArgument { name } => Message::MissingArgument { name },
+            UsageError::MissingOption { option } => Message::MissingOption { option },
+            UsageError::ConflictingOptions { first, second } => {
+                Message::ConflictingOptions { first, second }
+            }
+            UsageError::OptionRequires { name, required } => {
+                Message::OptionRequires { name, required }
+            }
+            UsageError::MissingOneOf { options } => Message::MissingOneOf { options },
+            UsageError::Multiple(_) => return None,
+            UsageError::InvalidOption {
+                name,
+                option,
+                value,
+                err,
+            } => match err {
+                OptionError::Unknown => Message::UnknownOption { name, option },
+                OptionError::Ambiguous(candidates) => Message::AmbiguousOption {
+                    name,
+                    option,
+                    candidates,
+                },
+                OptionError::MissingParameter => Message::MissingParameter { name, option },
+                OptionError::OptionAsParameter(arg) => {
+                    Message::OptionAsParameter { name, option, arg }
+                }
+                OptionError::UnexpectedParameter => Message::UnexpectedParameter { name, option },
+                OptionError::InvalidUnicode => match value {
+                    Some(value) => Message::InvalidUnicode {
+                        name,
+                        option,
+                        value,
+                    },
+                    None => Message::OptionFailed {
+                        name,
+                        option,
+                        err: &InvalidUnicode,
+                    },
+                },
+                OptionError::InvalidChoice(choices) => match value {
+                    Some(value) => Message::InvalidChoice {
+                        name,
+                        option,
+                        value,
+                        choices,
+                    },
+                    None => Message::ChoiceFailed {
+                        name,
+                        option,
+                        choices,
+                    },
+                },
+                OptionError::InvalidValue(err) => match value {
+                    Some(value) => Message::InvalidValue {
+                        name,
+                        option,
+                        value,
+                        err: err.as_ref(),
+                    },
+                    None => Message::OptionFailed {
+                        name,
+                        option,
+                        err: err.as_ref(),
+                    },
+                },
+            },
+        })
+    }
+
+    /// Display the error using messages from a catalog. The [`Display`] implementation for
+    /// `UsageError` uses the [`English`] catalog.
+    pub fn display<'a, C>(&'a self, catalog: &'a C) -> Localized<'a, T, C>
+    where
+        C: Catalog + ?Sized,
+    {
+        Localized {
+            err: self,
+            catalog,
+            quote: None,
+        }
+    }
+
+    /// Get the argument or option value for this error, if it has one.
+    pub(crate) fn value(&self) -> Option<&T> {
+        match self {
+            UsageError::InvalidArgument { arg } | UsageError::UnexpectedArgument { arg } => {
+                Some(arg)
+            }
+            UsageError::InvalidOption { value, .. } => value.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl<T> UsageError<T>
+where
+    T: ArgString + Debug,
+{
+    /// Display the error with argument values quoted for a POSIX shell, using the [`English`]
+    /// catalog. See [`Localized::quoted`].
+    pub fn quoted(&self) -> Localized<'_, T, English> {
+        self.display(&English).quoted()
+    }
 }
 
 impl<T> Display for UsageError<T>
@@ -76,32 +360,95 @@ where
     T: Debug,
 {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(&self.display(&English), f)
+    }
+}
+
+impl<T> Error for UsageError<T>
+where
+    T: Debug,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            UsageError::InvalidArgument { arg } => write!(f, "invalid argument {:?}", arg),
-            UsageError::UnexpectedArgument { arg } => write!(f, "unexpected argument {:?}", arg),
-            UsageError::MissingArgument { name } => write!(f, "missing argument <{}>", name),
-            UsageError::InvalidOption { name, value, err } => match err {
-                OptionError::Unknown => write!(f, "unknown option -{}", name),
-                OptionError::MissingParameter => write!(f, "option -{} requires a parameter", name),
-                OptionError::UnexpectedParameter => {
-                    write!(f, "option -{} does not accept a parameter", name)
-                }
-                OptionError::InvalidUnicode => write!(
-                    f,
-                    "invalid value {:?} for option -{}: invalid Unicode string",
-                    value.as_ref().unwrap(),
-                    name
-                ),
-                OptionError::InvalidValue(err) => write!(
-                    f,
-                    "invalid value {:?} for option -{}: {}",
-                    value.as_ref().unwrap(),
-                    name,
-                    err
-                ),
-            },
+            UsageError::InvalidOption {
+                err: OptionError::InvalidValue(err),
+                ..
+            } => Some(err.as_ref()),
+            _ => None,
         }
     }
 }
 
-impl<T> Error for UsageError<T> where T: Debug {}
+/// A collector for usage errors, for reporting every error at once instead of stopping at the
+/// first one.
+///
+/// ```
+/// use simpleargs::{Arg, Args, OptionError, UsageError, UsageErrors};
+/// let args = vec!["-a".to_owned(), "-b".to_owned()];
+/// let mut args = Args::from(args.into_iter());
+/// let mut errors = UsageErrors::new();
+/// loop {
+///     match args.next() {
+///         Arg::Positional(arg, _) => errors.push(UsageError::UnexpectedArgument { arg }),
+///         Arg::Named(arg) => {
+///             errors.check(arg.parse(|_, _| Err::<(), _>(OptionError::Unknown)));
+///         }
+///         Arg::End => break,
+///         Arg::Error(err) => errors.push(err),
+///     }
+/// }
+/// let err = errors.finish().unwrap_err();
+/// assert_eq!(err.to_string(), "unknown option -a\nunknown option -b");
+/// ```
+#[derive(Debug)]
+pub struct UsageErrors<T> {
+    errors: Vec<UsageError<T>>,
+}
+
+impl<T> Default for UsageErrors<T> {
+    fn default() -> Self {
+        UsageErrors::new()
+    }
+}
+
+impl<T> UsageErrors<T> {
+    /// Create an empty error collector.
+    pub fn new() -> Self {
+        UsageErrors { errors: Vec::new() }
+    }
+
+    /// Add an error to the collection.
+    pub fn push(&mut self, err: UsageError<T>) {
+        match err {
+            UsageError::Multiple(errors) => self.errors.extend(errors),
+            err => self.errors.push(err),
+        }
+    }
+
+    /// Add the error from a result to the collection, if it is an error. Returns the value if it
+    /// is not an error.
+    pub fn check<U>(&mut self, result: Result<U, UsageError<T>>) -> Option<U> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.push(err);
+                None
+            }
+        }
+    }
+
+    /// Return true if no errors have been collected.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Finish collecting errors. Returns the error if exactly one error was collected, or
+    /// [`UsageError::Multiple`] if more than one error was collected.
+    pub fn finish(mut self) -> Result<(), UsageError<T>> {
+        match self.errors.len() {
+            0 => Ok(()),
+            1 => Err(self.errors.pop().unwrap()),
+            _ => Err(UsageError::Multiple(self.errors)),
+        }
+    }
+}
@@ -15,6 +15,9 @@
 //! Options which take values can take values either as one argument, `-option=value`, or as two
 //! arguments, `-option value`.
 //!
+//! Other conventions, like GNU `getopt_long` or Windows `/option:value`, can be used instead by
+//! passing a different [`Syntax`] to [`Args::with_syntax`].
+//!
 //! [`String`]: std::string::String
 //! [`OsString`]: std::ffi::OsString
 //!
@@ -37,7 +40,7 @@
 //!     let mut xvalue: Option<i32> = None;
 //!     loop {
 //!         match args.next() {
-//!             Arg::Positional(arg) => if input.is_some() {
+//!             Arg::Positional(arg, _) => if input.is_some() {
 //!                 return Err(UsageError::UnexpectedArgument { arg });
 //!             } else {
 //!                 input = Some(arg)
@@ -71,20 +74,121 @@
 #![deny(missing_docs)]
 
 pub mod arg;
+mod choice;
+mod constraint;
 mod error;
+mod message;
+mod session;
+pub mod shell;
+mod stdio;
+pub mod testing;
+pub mod windows;
 
+use constraint::Seen;
+use std::collections::VecDeque;
+use std::env;
 use std::ffi::OsStr;
+use std::sync::Arc;
+
+use arg::Token;
+pub use arg::{ArgString, NameChars, ParsedArg, Syntax};
+pub use choice::{Choice, Choices};
+pub use constraint::Constraints;
+pub use error::{OptionError, UsageError, UsageErrors};
+pub use message::{Catalog, English, Localized, Message};
+pub use session::{Completion, LineError, Session};
+pub use stdio::{Input, OpenError, Output};
+
+/// How positional arguments affect option processing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    /// Options may appear after positional arguments. This is the default, and is how GNU
+    /// `getopt` works.
+    #[default]
+    Intermixed,
+    /// The first positional argument ends option processing, and all arguments after it are
+    /// positional. This is how POSIX `getopt` works, and is useful for wrapper tools, so that
+    /// `tool run cmd -x` does not treat `-x` as an option for `tool`.
+    StopAtPositional,
+}
 
-pub use arg::{ArgString, ParsedArg};
-pub use error::{OptionError, UsageError};
+impl Order {
+    /// Get the order requested by the environment: [`Order::StopAtPositional`] if the
+    /// `POSIXLY_CORRECT` environment variable is set, and [`Order::Intermixed`] otherwise.
+    pub fn from_env() -> Order {
+        match env::var_os("POSIXLY_CORRECT") {
+            Some(_) => Order::StopAtPositional,
+            None => Order::Intermixed,
+        }
+    }
+}
+
+/// Whether an option value passed as a separate argument may look like an option.
+///
+/// This only affects values passed as separate arguments, like `--output <file>`. Values attached
+/// to the option, like `--output=--verbose`, are always accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValuePolicy {
+    /// Accept any argument as a value. This is the default.
+    #[default]
+    Any,
+    /// Reject arguments which look like options, so `--output --verbose` is reported as
+    /// [`OptionError::OptionAsParameter`] instead of setting the output to `--verbose`. A single
+    /// `-` and negative numbers like `-5` are still accepted.
+    RejectOptions,
+}
+
+/// A built-in option which asks the program to do something other than its usual job. Found by
+/// [`Args::builtin`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    /// Print help and exit, like `--help`.
+    Help,
+    /// Print the version and exit, like `--version`.
+    Version,
+}
 
 /// A stream of arguments.
-pub struct Args<T> {
+pub struct Args<T>
+where
+    T: Iterator,
+{
     args: T,
+    /// Arguments which have been read ahead or pushed back, in order.
+    pending: VecDeque<T::Item>,
     allow_options: bool,
+    /// The order, if it was set instead of taken from the syntax.
+    order: Option<Order>,
+    value_policy: ValuePolicy,
+    /// The syntax, which may be shared with other argument streams.
+    syntax: Arc<Syntax>,
+    /// The rest of a group of short options, like "bc" in "-abc".
+    cluster: Option<T::Item>,
+    /// The full argument containing a group of short options, like "-abc".
+    cluster_raw: Option<T::Item>,
+    /// The argument which ended option processing, like "--".
+    separator: Option<T::Item>,
+    after_separator: bool,
+    /// Long option names which may be abbreviated.
+    abbreviations: Vec<String>,
+    /// Names of options which request help.
+    help: Vec<String>,
+    /// Names of options which request the version.
+    version: Vec<String>,
+    /// Names of options which take a value, so [`Args::builtin`] can skip their values.
+    values: Vec<String>,
+    /// Options which were parsed successfully, in order.
+    seen: Vec<Seen>,
+    /// Arguments forwarded by [`NamedArgument::forward`], in order.
+    passthrough: Vec<T::Item>,
+    /// Names of forwarded options which take a value as a separate argument.
+    passthrough_values: Vec<String>,
 }
 
-impl<T> Args<T> {
+impl<T> Args<T>
+where
+    T: Iterator,
+{
     /// Create an argument stream from an argument iterator. The program name should not be included
     /// in the argument stream.
     ///
@@ -100,13 +204,339 @@ impl<T> Args<T> {
     pub fn from(args: T) -> Self {
         Args {
             args,
+            pending: VecDeque::new(),
             allow_options: true,
+            order: None,
+            value_policy: ValuePolicy::Any,
+            syntax: Arc::new(Syntax::new()),
+            cluster: None,
+            cluster_raw: None,
+            separator: None,
+            after_separator: false,
+            abbreviations: Vec::new(),
+            help: Vec::new(),
+            version: Vec::new(),
+            values: Vec::new(),
+            seen: Vec::new(),
+            passthrough: Vec::new(),
+            passthrough_values: Vec::new(),
+        }
+    }
+
+    /// Set the syntax used to parse options. The default is [`Syntax::new`].
+    ///
+    /// ```
+    /// use simpleargs::{Args, Syntax};
+    /// // Parse options like getopt_long: --verbose, -v, -vx, -ofile.
+    /// let args = Args::from(vec!["-vofile".to_owned()].into_iter()).with_syntax(Syntax::gnu());
+    /// ```
+    ///
+    /// The syntax can also be passed as an `Arc<Syntax>`, so that many argument streams can share
+    /// it without copying it.
+    pub fn with_syntax<S: Into<Arc<Syntax>>>(mut self, syntax: S) -> Self {
+        self.syntax = syntax.into();
+        self
+    }
+
+    /// Change the syntax, copying it if it is shared.
+    fn map_syntax<F: FnOnce(Syntax) -> Syntax>(mut self, f: F) -> Self {
+        self.syntax = Arc::new(f(Syntax::clone(&self.syntax)));
+        self
+    }
+
+    /// Set the characters accepted in option names.
+    ///
+    /// Arguments which look like options but have names containing other characters are returned
+    /// as [`UsageError::InvalidArgument`]. The default is [`NameChars::Standard`].
+    ///
+    /// ```
+    /// use simpleargs::{Args, NameChars};
+    /// let args = Args::from(vec!["--foo.bar=1".to_owned()].into_iter())
+    ///     .with_name_chars(NameChars::Dotted);
+    /// ```
+    pub fn with_name_chars(self, names: NameChars) -> Self {
+        self.map_syntax(|syntax| syntax.names(names))
+    }
+
+    /// Set the characters which separate option names from values. The default is `"="`.
+    ///
+    /// See [`Syntax::separators`].
+    ///
+    /// ```
+    /// use simpleargs::Args;
+    /// // Accept --opt=value and --opt:value.
+    /// let args = Args::from(vec!["--opt:value".to_owned()].into_iter()).with_separators("=:");
+    /// ```
+    pub fn with_separators(self, separators: &str) -> Self {
+        self.map_syntax(|syntax| syntax.separators(separators))
+    }
+
+    /// Set the names of options which take values attached directly to the name, like `-ofile` or
+    /// `-Dname=value`.
+    ///
+    /// See [`Syntax::attached`].
+    ///
+    /// ```
+    /// use simpleargs::Args;
+    /// let args = Args::from(vec!["-Dname=val".to_owned()].into_iter()).with_attached(vec!["D"]);
+    /// ```
+    pub fn with_attached<I, S>(self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.map_syntax(|syntax| syntax.attached(names))
+    }
+
+    /// Set how positional arguments affect option processing. The default is the order set by
+    /// the syntax, see [`Syntax::order`].
+    ///
+    /// ```
+    /// use simpleargs::{Args, Order};
+    /// let args = Args::from(std::env::args().skip(1)).with_order(Order::from_env());
+    /// ```
+    pub fn with_order(mut self, order: Order) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Change how positional arguments affect option processing, for the arguments which have not
+    /// been parsed yet.
+    ///
+    /// For example, a tool with subcommands could parse its own options in any order, but switch
+    /// to [`Order::StopAtPositional`] after seeing the `run` subcommand, so that the options after
+    /// the command to run are passed to that command.
+    pub fn set_order(&mut self, order: Order) {
+        self.order = Some(order);
+    }
+
+    /// Return true if the first positional argument ends option processing.
+    fn stop_at_positional(&self) -> bool {
+        self.order.unwrap_or_else(|| self.syntax.get_order()) == Order::StopAtPositional
+    }
+
+    /// Set whether option values passed as separate arguments may look like options. The default
+    /// is [`ValuePolicy::Any`]. This can be changed for individual options with [`Value::policy`].
+    ///
+    /// ```
+    /// use simpleargs::{Args, ValuePolicy};
+    /// let args = Args::from(std::env::args().skip(1)).with_value_policy(ValuePolicy::RejectOptions);
+    /// ```
+    pub fn with_value_policy(mut self, policy: ValuePolicy) -> Self {
+        self.value_policy = policy;
+        self
+    }
+
+    /// Return true if arguments are being parsed as options.
+    ///
+    /// Option processing is disabled by `--`, by the first positional argument if the order is
+    /// [`Order::StopAtPositional`], or by calling [`set_options_enabled`]. When disabled, every
+    /// argument is returned as positional.
+    ///
+    /// [`set_options_enabled`]: Args::set_options_enabled
+    pub fn options_enabled(&self) -> bool {
+        self.allow_options
+    }
+
+    /// Enable or disable option processing for the arguments which have not been parsed yet.
+    ///
+    /// This can be used to parse options again after `--`, for example, to parse the arguments for
+    /// a subcommand.
+    pub fn set_options_enabled(&mut self, enabled: bool) {
+        self.allow_options = enabled;
+    }
+
+    /// Return true if `--` has been seen. Each positional argument also says whether it appeared
+    /// after `--`, see [`Arg::Positional`].
+    pub fn after_separator(&self) -> bool {
+        self.after_separator
+    }
+
+    /// Take the `--` argument, if it has been seen, exactly as it was passed in.
+    ///
+    /// This is useful for tools which forward arguments to another program, since the separator is
+    /// otherwise not returned by [`next`].
+    ///
+    /// [`next`]: Args::next
+    pub fn take_separator(&mut self) -> Option<T::Item> {
+        self.separator.take()
+    }
+
+    /// Allow long options to be abbreviated to any unambiguous prefix of one of the given names.
+    ///
+    /// When an option is abbreviated, [`NamedArgument::parse`] passes the full name to the
+    /// callback. For example, with `["verbose", "version"]`, `--verb` is passed as `"verbose"`. If
+    /// the abbreviation matches more than one name, like `--ver`, then the option is rejected with
+    /// [`OptionError::Ambiguous`]. An exact match is never ambiguous, and names which match
+    /// nothing, as well as short options, are passed through unchanged.
+    ///
+    /// ```
+    /// use simpleargs::Args;
+    /// let args = Args::from(vec!["--verb".to_owned()].into_iter())
+    ///     .with_abbreviations(vec!["verbose", "version"]);
+    /// ```
+    pub fn with_abbreviations<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.abbreviations = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the names of options which request help, like `["help", "h"]`. See [`Args::builtin`].
+    pub fn with_help<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.help = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the names of options which request the version, like `["version"]`. See
+    /// [`Args::builtin`].
+    pub fn with_version<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.version = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the names of options which take a value, like `["o", "output"]`. See
+    /// [`Args::builtin`].
+    pub fn with_values<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.values = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the names of forwarded options which take a value as a separate argument, like
+    /// `["o", "output"]`. When one of these options is forwarded without an attached value, the
+    /// next argument is forwarded with it. See [`NamedArgument::forward`].
+    pub fn with_passthrough_values<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.passthrough_values = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Get the arguments forwarded so far with [`NamedArgument::forward`], exactly as they were
+    /// passed in.
+    pub fn passthrough(&self) -> &[T::Item] {
+        &self.passthrough
+    }
+
+    /// Take the arguments forwarded so far with [`NamedArgument::forward`], exactly as they were
+    /// passed in.
+    pub fn take_passthrough(&mut self) -> Vec<T::Item> {
+        std::mem::take(&mut self.passthrough)
+    }
+
+    /// Expand an abbreviated option name. Returns the full name, or None if the name is not an
+    /// abbreviation.
+    fn expand(&self, name: &str) -> Result<Option<String>, OptionError> {
+        if self.abbreviations.iter().any(|n| n == name) {
+            return Ok(None);
+        }
+        let mut matches: Vec<&String> = self
+            .abbreviations
+            .iter()
+            .filter(|n| n.starts_with(name))
+            .collect();
+        match matches.len() {
+            0 => Ok(None),
+            1 => Ok(matches.pop().cloned()),
+            _ => Err(OptionError::Ambiguous(
+                matches.into_iter().cloned().collect(),
+            )),
+        }
+    }
+
+    /// Look at the next argument in the stream without consuming it.
+    ///
+    /// The argument is returned exactly as it was passed in, without being parsed. If the previous
+    /// argument was a group of short options like `-abc`, the rest of the group is not included.
+    pub fn peek(&mut self) -> Option<&T::Item> {
+        if self.pending.is_empty() {
+            let arg = self.args.next()?;
+            self.pending.push_back(arg);
         }
+        self.pending.front()
     }
 
-    /// Get the remaining unparsed arguments in the stream.
-    pub fn rest(self) -> T {
-        self.args
+    /// Push an argument back onto the front of the stream, so it is returned next.
+    ///
+    /// The argument is parsed again when it is read, so pushing back an option returns it as an
+    /// option. Arguments can be pushed back more than once, and the most recently pushed argument
+    /// is returned first. If the last option returned by [`next`] was part of a group of short
+    /// options, like `-a` in `-ab`, the rest of the group is returned before the pushed-back
+    /// argument, the same way that [`peek`] looks past the rest of the group.
+    ///
+    /// [`next`]: Args::next
+    /// [`peek`]: Args::peek
+    ///
+    /// ```
+    /// use simpleargs::{Arg, Args};
+    /// let mut args = Args::from(vec!["a".to_owned()].into_iter());
+    /// args.unread("-b".to_owned());
+    /// assert!(matches!(args.next(), Arg::Named(_)));
+    /// ```
+    pub fn unread(&mut self, arg: T::Item) {
+        self.pending.push_front(arg);
+    }
+
+    /// Check that the options parsed so far satisfy a set of constraints.
+    ///
+    /// This should be called after all arguments are parsed. Only options which were parsed
+    /// successfully with [`NamedArgument::parse`] count as used.
+    ///
+    /// ```
+    /// use simpleargs::{Arg, Args, Constraints};
+    /// let mut args = Args::from(vec!["-q".to_owned(), "-v".to_owned()].into_iter());
+    /// while let Arg::Named(arg) = args.next() {
+    ///     arg.parse(|_, _| Ok(())).unwrap();
+    /// }
+    /// let constraints = Constraints::new().conflicts("q", "v");
+    /// assert!(args.check(&constraints).is_err());
+    /// ```
+    pub fn check(&self, constraints: &Constraints) -> Result<(), UsageError<T::Item>> {
+        constraints.check(&self.seen, &self.syntax)
+    }
+
+    /// Get the next argument without parsing it.
+    fn next_raw(&mut self) -> Option<T::Item> {
+        self.pending.pop_front().or_else(|| self.args.next())
+    }
+
+    /// Get the remaining unparsed arguments in the stream, including any arguments that were
+    /// peeked at or pushed back.
+    ///
+    /// If the last option returned by [`next`] was part of a group of short options, like `-a` in
+    /// `-ab`, the rest of the group is dropped, since it cannot be returned as an argument by
+    /// itself. To keep it, call [`next`] until the group is finished before calling this.
+    ///
+    /// ```
+    /// use simpleargs::{Arg, Args, Syntax};
+    /// let input = vec!["-ab".to_owned(), "c".to_owned()];
+    /// let mut args = Args::from(input.into_iter()).with_syntax(Syntax::gnu());
+    /// if let Arg::Named(arg) = args.next() {
+    ///     arg.parse(|_, _| Ok(())).unwrap();
+    /// }
+    /// // The "b" in "-ab" is dropped.
+    /// assert_eq!(args.rest().collect::<Vec<String>>(), ["c"]);
+    /// ```
+    ///
+    /// [`next`]: Args::next
+    pub fn rest(self) -> impl Iterator<Item = T::Item> {
+        self.pending.into_iter().chain(self.args)
     }
 }
 
@@ -115,35 +545,161 @@ where
     T: Iterator,
     <T as Iterator>::Item: ArgString,
 {
+    /// Look through the remaining arguments for an option which requests help or the version, as
+    /// set with [`Args::with_help`] and [`Args::with_version`]. Returns the first one found.
+    ///
+    /// Call this before parsing, so `tool --bogus --help` prints help instead of reporting that
+    /// `--bogus` is unknown. No arguments are consumed, but the whole remaining input iterator is
+    /// read and buffered, so this should not be used with an endless stream of arguments.
+    /// Arguments after `--` are not options, and neither are arguments after the first positional
+    /// argument with [`Order::StopAtPositional`].
+    ///
+    /// Options are found the same way [`next`] finds them: inside groups of short options like
+    /// `-vh`, and as abbreviations set with [`Args::with_abbreviations`]. Options named with
+    /// [`Args::with_values`] take the rest of their group or the next argument as a value, which
+    /// is skipped. Other options are assumed not to take values, so `--output --help` and `-oh`
+    /// count as requests for help unless `output` and `o` are named, and `-o file` counts as an
+    /// option followed by a positional argument unless `o` is named.
+    ///
+    /// ```
+    /// use simpleargs::{Args, Builtin};
+    /// let input = vec!["--bogus".to_owned(), "--help".to_owned()];
+    /// let mut args = Args::from(input.into_iter()).with_help(vec!["help", "h"]);
+    /// assert_eq!(args.builtin(), Some(Builtin::Help));
+    ///
+    /// let input = vec!["--output".to_owned(), "--help".to_owned()];
+    /// let mut args = Args::from(input.into_iter())
+    ///     .with_help(vec!["help", "h"])
+    ///     .with_values(vec!["output"]);
+    /// assert_eq!(args.builtin(), None);
+    /// ```
+    ///
+    /// [`next`]: Args::next
+    pub fn builtin(&mut self) -> Option<Builtin> {
+        if !self.allow_options {
+            return None;
+        }
+        self.pending.extend(&mut self.args);
+        let find = |name: &str| {
+            if self.help.iter().any(|n| n == name) {
+                Some(Builtin::Help)
+            } else if self.version.iter().any(|n| n == name) {
+                Some(Builtin::Version)
+            } else {
+                None
+            }
+        };
+        let mut iter = self.pending.iter();
+        while let Some(arg) = iter.next() {
+            if self.syntax.is_end_marker(arg) {
+                break;
+            }
+            let (mut arg, short) = match self.syntax.parse_option(arg.arg_tail(0)) {
+                Ok((ParsedArg::Positional(_), _)) if self.stop_at_positional() => break,
+                Ok((arg, Some(token))) => (arg, token.short),
+                _ => continue,
+            };
+            // Check each option in a group of short options, like "-vh".
+            loop {
+                let (name, rest) = match arg {
+                    ParsedArg::Named(name, None) => (name, None),
+                    ParsedArg::Cluster(name, rest) => (name, Some(rest)),
+                    _ => break,
+                };
+                let name = match self.expand(&name) {
+                    Ok(Some(full)) if !short => full,
+                    _ => name,
+                };
+                if let Some(builtin) = find(&name) {
+                    return Some(builtin);
+                }
+                if self.values.contains(&name) {
+                    // The value is the rest of the group, or else the next argument.
+                    if rest.is_none() {
+                        iter.next();
+                    }
+                    break;
+                }
+                arg = match rest.map(|rest| self.syntax.parse_cluster(rest)) {
+                    Some(Ok(next)) => next,
+                    _ => break,
+                };
+            }
+        }
+        None
+    }
+
     /// Get the next argument in the stream.
     pub fn next<'a>(&'a mut self) -> Arg<'a, T> {
-        let arg = match self.args.next() {
+        if let Some(rest) = self.cluster.take() {
+            let raw = self.cluster_raw.take().unwrap();
+            let arg = match self.syntax.parse_cluster(rest) {
+                Err(_) => return Arg::Error(UsageError::InvalidArgument { arg: raw }),
+                Ok(arg) => arg,
+            };
+            let spelling = match &arg {
+                ParsedArg::Named(name, _) | ParsedArg::Cluster(name, _) => {
+                    self.syntax.short_spelling(name)
+                }
+                _ => unreachable!(),
+            };
+            let token = Token {
+                raw,
+                spelling,
+                short: true,
+                grouped: true,
+            };
+            return self.named(arg, token);
+        }
+        let arg = match self.next_raw() {
             None => return Arg::End,
             Some(arg) => arg,
         };
         if !self.allow_options {
-            return Arg::Positional(arg);
+            return Arg::Positional(arg, self.after_separator);
+        }
+        if self.syntax.is_end_marker(&arg) {
+            self.allow_options = false;
+            self.separator = Some(arg);
+            self.after_separator = true;
+            return self.next();
         }
-        let arg = match arg.parse_arg() {
+        let (arg, token) = match self.syntax.parse_option(arg) {
             Err(arg) => return Arg::Error(UsageError::InvalidArgument { arg }),
             Ok(arg) => arg,
         };
         match arg {
-            ParsedArg::Positional(arg) => Arg::Positional(arg),
-            ParsedArg::EndOfFlags => {
-                self.allow_options = false;
-                match self.args.next() {
-                    None => Arg::End,
-                    Some(arg) => Arg::Positional(arg),
+            ParsedArg::Positional(arg) => {
+                if self.stop_at_positional() {
+                    self.allow_options = false;
                 }
+                Arg::Positional(arg, self.after_separator)
             }
-            ParsedArg::Named(name, data) => Arg::Named(NamedArgument {
-                name,
-                data,
-                args: self,
-            }),
+            // Handled above, so the separator can be kept.
+            ParsedArg::EndOfFlags => unreachable!(),
+            arg => self.named(arg, token.unwrap()),
         }
     }
+
+    /// Create a named argument from a parsed option.
+    fn named(&mut self, arg: ParsedArg<T::Item>, token: Token<T::Item>) -> Arg<'_, T> {
+        let (name, data) = match arg {
+            ParsedArg::Named(name, data) => (name, data),
+            ParsedArg::Cluster(name, rest) => {
+                // The rest is either this option's value or more options.
+                self.cluster = Some(rest);
+                self.cluster_raw = Some(token.raw.arg_tail(0));
+                (name, None)
+            }
+            _ => unreachable!(),
+        };
+        Arg::Named(NamedArgument {
+            name,
+            token,
+            data,
+            args: self,
+        })
+    }
 }
 
 /// A single argument in a stream of arguments.
@@ -151,8 +707,19 @@ pub enum Arg<'a, T>
 where
     T: Iterator,
 {
-    /// A positional argument.
-    Positional(T::Item),
+    /// A positional argument, and true if it appeared after `--`.
+    ///
+    /// ```
+    /// use simpleargs::{Arg, Args};
+    /// let input = vec!["a".to_owned(), "--".to_owned(), "b".to_owned()];
+    /// let mut args = Args::from(input.into_iter());
+    /// let mut after = Vec::new();
+    /// while let Arg::Positional(arg, after_separator) = args.next() {
+    ///     after.push((arg, after_separator));
+    /// }
+    /// assert_eq!(after, vec![("a".to_owned(), false), ("b".to_owned(), true)]);
+    /// ```
+    Positional(T::Item, bool),
     /// A named argument, possibly with an associated value.
     Named(NamedArgument<'a, T>),
     /// End of the argument stream.
@@ -167,10 +734,28 @@ where
     T: Iterator,
 {
     name: String,
+    token: Token<T::Item>,
     data: Option<<T as Iterator>::Item>,
     args: &'a mut Args<T>,
 }
 
+impl<'a, T> NamedArgument<'a, T>
+where
+    T: Iterator,
+{
+    /// Get the option as the user typed it, including the prefix but not the value, like
+    /// `--name`.
+    pub fn spelling(&self) -> &str {
+        &self.token.spelling
+    }
+
+    /// Get the full argument which contained the option, exactly as it was passed in. For a group
+    /// of short options like `-abc`, this is the whole group.
+    pub fn raw(&self) -> &T::Item {
+        &self.token.raw
+    }
+}
+
 impl<'a, T> NamedArgument<'a, T>
 where
     T: Iterator,
@@ -179,7 +764,8 @@ where
     /// Parse the named command-line option.
     ///
     /// The option name and value are passed to the supplied function. Any errors that the function
-    /// returns are annotated with information about the option.
+    /// returns are annotated with information about the option. If abbreviations are enabled with
+    /// [`Args::with_abbreviations`], the full option name is passed.
     ///
     /// An error is returned if the user supplied a value, but [`as_str`] or [`as_osstr`] is not
     /// called.
@@ -190,24 +776,141 @@ where
     where
         for<'b> F: FnOnce(&'b str, Value<'b, T>) -> Result<U, OptionError>,
     {
+        self.parse_impl(false, f).map(Option::unwrap)
+    }
+
+    /// Parse the named command-line option, or forward it if it is unknown.
+    ///
+    /// This is like [`parse`], except that if the function returns [`OptionError::Unknown`]
+    /// without taking the value, the option is forwarded with [`forward`] and `None` is returned.
+    /// This is useful for wrappers which handle a few options and pass the rest to another
+    /// program.
+    ///
+    /// ```
+    /// use simpleargs::{Arg, Args, OptionError};
+    /// let input = vec!["-v", "--color=always", "-o", "out", "file"];
+    /// let mut args = Args::from(input.into_iter().map(str::to_owned))
+    ///     .with_passthrough_values(vec!["o"]);
+    /// let mut verbose = false;
+    /// while let Arg::Named(arg) = args.next() {
+    ///     arg.parse_or_forward(|name, _| match name {
+    ///         "v" => {
+    ///             verbose = true;
+    ///             Ok(())
+    ///         }
+    ///         _ => Err(OptionError::Unknown),
+    ///     })
+    ///     .unwrap();
+    /// }
+    /// assert!(verbose);
+    /// assert_eq!(args.passthrough(), ["--color=always", "-o", "out"]);
+    /// ```
+    ///
+    /// [`parse`]: NamedArgument::parse
+    /// [`forward`]: NamedArgument::forward
+    pub fn parse_or_forward<U, F>(self, f: F) -> Result<Option<U>, UsageError<T::Item>>
+    where
+        for<'b> F: FnOnce(&'b str, Value<'b, T>) -> Result<U, OptionError>,
+    {
+        self.parse_impl(true, f)
+    }
+
+    /// Forward the option unchanged, by adding it to the list returned by [`Args::passthrough`].
+    ///
+    /// The argument containing the option is forwarded exactly as it was passed in, including any
+    /// attached value like `--name=value`. If the option was passed without an attached value and
+    /// its name was given to [`Args::with_passthrough_values`], the next argument is forwarded too.
+    ///
+    /// A short option which starts a group, like `-x` in `-xyz`, is forwarded with the rest of
+    /// the group, which is not parsed. A short option later in a group, after an option which was
+    /// not forwarded, cannot be passed on unchanged, so it is rejected as unknown instead.
+    pub fn forward(self) -> Result<(), UsageError<T::Item>> {
         let NamedArgument {
             name,
+            token,
+            data,
+            args,
+        } = self;
+        if token.grouped {
+            return Err(UsageError::InvalidOption {
+                name,
+                option: token.spelling,
+                value: data,
+                err: OptionError::Unknown,
+            });
+        }
+        let mut separate = data.is_none() && args.passthrough_values.contains(&name);
+        if token.short && args.cluster.take().is_some() {
+            // The rest of the group goes with the option, as part of the same argument.
+            args.cluster_raw = None;
+            separate = false;
+        }
+        args.passthrough.push(token.raw);
+        if separate {
+            if let Some(value) = args.next_raw() {
+                args.passthrough.push(value);
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_impl<U, F>(self, forward: bool, f: F) -> Result<Option<U>, UsageError<T::Item>>
+    where
+        for<'b> F: FnOnce(&'b str, Value<'b, T>) -> Result<U, OptionError>,
+    {
+        let NamedArgument {
+            name: typed,
+            token,
             mut data,
             args,
         } = self;
+        let expanded = if token.short {
+            Ok(None)
+        } else {
+            args.expand(&typed)
+        };
+        let name = match expanded {
+            Ok(full) => full.unwrap_or_else(|| typed.clone()),
+            Err(err) => {
+                return Err(UsageError::InvalidOption {
+                    name: typed,
+                    option: token.spelling,
+                    value: data,
+                    err,
+                })
+            }
+        };
         let mut consumed = false;
         let err = match f(
             &name,
             Value {
+                option: &token.spelling,
                 data: &mut data,
-                args,
+                policy: args.value_policy,
+                args: &mut *args,
                 consumed: &mut consumed,
             },
         ) {
+            Err(OptionError::Unknown) if forward && !consumed => {
+                let arg = NamedArgument {
+                    name: typed,
+                    token,
+                    data,
+                    args,
+                };
+                return arg.forward().map(|()| None);
+            }
             Err(err) => err,
             Ok(r) => {
                 if consumed || data.is_none() {
-                    return Ok(r);
+                    let option = token.spelling;
+                    let prefix = option[..option.len() - typed.len()].to_owned();
+                    args.seen.push(Seen {
+                        name,
+                        option,
+                        prefix,
+                    });
+                    return Ok(Some(r));
                 } else {
                     OptionError::UnexpectedParameter
                 }
@@ -215,6 +918,7 @@ where
         };
         Err(UsageError::InvalidOption {
             name,
+            option: token.spelling,
             value: data,
             err,
         })
@@ -228,27 +932,34 @@ pub struct Value<'a, T>
 where
     T: Iterator,
 {
+    /// The option as the user typed it, like "--name".
+    option: &'a str,
     data: &'a mut Option<<T as Iterator>::Item>,
     args: &'a mut Args<T>,
     consumed: &'a mut bool,
+    policy: ValuePolicy,
 }
 
 impl<'a, T> Value<'a, T>
 where
     T: Iterator,
 {
-    /// Get the associated value.
+    /// Look at the associated value without consuming it.
     ///
-    /// Returns an error if the user did not supply a value.
-    fn value(self) -> Result<&'a T::Item, OptionError> {
-        *self.consumed = true;
-        match self.data {
-            Some(x) => Ok(x),
-            None => match self.args.args.next() {
-                Some(x) => Ok(self.data.get_or_insert(x)),
-                None => Err(OptionError::MissingParameter),
-            },
+    /// This is the value that [`as_str`] or [`as_osstr`] would return: the value attached to the
+    /// option, as in `-opt=value`, or else the next argument. This can be used for options with
+    /// optional values, by only taking the next argument if it does not look like an option.
+    ///
+    /// [`as_str`]: Value::as_str
+    /// [`as_osstr`]: Value::as_osstr
+    pub fn peek(&mut self) -> Option<&T::Item> {
+        if self.data.is_some() {
+            return self.data.as_ref();
         }
+        if self.args.cluster.is_some() {
+            return self.args.cluster.as_ref();
+        }
+        self.args.peek()
     }
 }
 
@@ -257,6 +968,56 @@ where
     T: Iterator,
     <T as Iterator>::Item: ArgString,
 {
+    /// Set whether the value may look like an option, if it is passed as a separate argument. This
+    /// overrides the policy set with [`Args::with_value_policy`].
+    ///
+    /// ```
+    /// # use simpleargs::{Arg, Args, OptionError, ValuePolicy};
+    /// # let mut args = Args::from(vec!["--output".to_owned(), "--verbose".to_owned()].into_iter());
+    /// # if let Arg::Named(arg) = args.next() {
+    /// let r = arg.parse(|name, value| match name {
+    ///     "output" => value.policy(ValuePolicy::RejectOptions).as_str().map(drop),
+    ///     _ => Err(OptionError::Unknown),
+    /// });
+    /// assert_eq!(
+    ///     r.unwrap_err().to_string(),
+    ///     "option --output requires a parameter; got option --verbose",
+    /// );
+    /// # }
+    /// ```
+    pub fn policy(mut self, policy: ValuePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Get the associated value.
+    ///
+    /// Returns an error if the user did not supply a value.
+    fn value(self) -> Result<&'a T::Item, OptionError> {
+        *self.consumed = true;
+        if let Some(x) = self.data {
+            return Ok(x);
+        }
+        // The rest of a group of short options is attached, like "-ofile".
+        if let Some(x) = self.args.cluster.take() {
+            self.args.cluster_raw = None;
+            return Ok(self.data.get_or_insert(x));
+        }
+        if self.policy == ValuePolicy::RejectOptions {
+            self.args.peek();
+            if let Some(x) = self.args.pending.front() {
+                if self.args.syntax.is_option(x) {
+                    let arg = x.to_osstr().to_string_lossy().into_owned();
+                    return Err(OptionError::OptionAsParameter(arg));
+                }
+            }
+        }
+        match self.args.next_raw() {
+            Some(x) => Ok(self.data.get_or_insert(x)),
+            None => Err(OptionError::MissingParameter),
+        }
+    }
+
     /// Get the associated value as a string.
     ///
     /// Note that ownership of the string is not passed. Ownership is kept by the NamedArgument so
@@ -279,6 +1040,76 @@ where
     pub fn as_osstr(self) -> Result<&'a OsStr, OptionError> {
         self.value().map(ArgString::to_osstr)
     }
+
+    /// Get the associated value as one of a list of choices.
+    ///
+    /// Returns an error if the user did not supply a value, or if the value does not match exactly
+    /// one of the choices.
+    ///
+    /// ```
+    /// # use simpleargs::{Arg, Args, Choices, OptionError};
+    /// # let mut args = Args::from(vec!["--format=xml".to_owned()].into_iter());
+    /// # if let Arg::Named(arg) = args.next() {
+    /// let formats = Choices::new(&[("json", 1), ("yaml", 2), ("text", 3)]);
+    /// let r = arg.parse(|name, value| match name {
+    ///     "format" => value.choose(&formats).map(drop),
+    ///     _ => Err(OptionError::Unknown),
+    /// });
+    /// assert_eq!(
+    ///     r.unwrap_err().to_string(),
+    ///     "invalid value \"xml\" for option --format: expected one of json, yaml, text",
+    /// );
+    /// # }
+    /// ```
+    pub fn choose<'c, C>(self, choices: &Choices<'c, C>) -> Result<&'c C, OptionError> {
+        choices.find(self.as_str()?)
+    }
+
+    /// Get the associated value as a [`Choice`], like an enum.
+    ///
+    /// Returns an error if the user did not supply a value, or if the value does not match exactly
+    /// one of the choices.
+    pub fn as_choice<C>(self) -> Result<C, OptionError>
+    where
+        C: Choice + Clone,
+    {
+        self.choose(&C::choices()).cloned()
+    }
+
+    /// Get the associated value as an input file, which is standard input if the value is `-`.
+    ///
+    /// Returns an error if the user did not supply a value.
+    ///
+    /// ```
+    /// # use simpleargs::{Arg, Args, Input, OptionError};
+    /// # let mut args = Args::from(vec!["--input=-".to_owned()].into_iter());
+    /// let mut input: Option<Input> = None;
+    /// # if let Arg::Named(arg) = args.next() {
+    /// arg.parse(|name, value| match name {
+    ///     "input" => {
+    ///         input = Some(value.as_input()?);
+    ///         Ok(())
+    ///     }
+    ///     _ => Err(OptionError::Unknown),
+    /// })?;
+    /// # }
+    /// // Later, after all arguments are parsed:
+    /// let reader = input.unwrap().open()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn as_input(self) -> Result<Input, OptionError> {
+        let option = self.option;
+        self.as_osstr().map(|path| Input::from_option(path, option))
+    }
+
+    /// Get the associated value as an output file, which is standard output if the value is `-`.
+    ///
+    /// Returns an error if the user did not supply a value.
+    pub fn as_output(self) -> Result<Output, OptionError> {
+        let option = self.option;
+        self.as_osstr()
+            .map(|path| Output::from_option(path, option))
+    }
 }
 
 #[cfg(test)]
@@ -300,7 +1131,7 @@ mod test {
         let mut xvalue = None;
         loop {
             match args.next() {
-                Arg::Positional(x) => positional.push(x),
+                Arg::Positional(x, _) => positional.push(x),
                 Arg::Named(arg) => arg.parse(|name, arg| match name {
                     "flag" => {
                         flag = true;
@@ -341,13 +1172,18 @@ mod test {
     #[test]
     fn no_param() {
         let r = parse_args(&["--x"]);
-        if let Err(e) = &r {
-            if let UsageError::InvalidOption { name, value, err } = e {
-                assert_eq!(name, "x");
-                assert!(value.is_none());
-                if let OptionError::MissingParameter = err {
-                    return;
-                }
+        if let Err(UsageError::InvalidOption {
+            name,
+            option,
+            value,
+            err,
+        }) = &r
+        {
+            assert_eq!(name, "x");
+            assert_eq!(option, "--x");
+            assert!(value.is_none());
+            if let OptionError::MissingParameter = err {
+                return;
             }
         }
         panic!("incorrect result: {:?}", r);
@@ -356,15 +1192,693 @@ mod test {
     #[test]
     fn bad_param() {
         let r = parse_args(&["-x", "0q"]);
-        if let Err(e) = &r {
-            if let UsageError::InvalidOption { name, value, err } = e {
-                assert_eq!(name, "x");
-                assert_eq!(value, &Some("0q".to_owned()));
-                if let OptionError::InvalidValue(_) = err {
-                    return;
-                }
+        if let Err(UsageError::InvalidOption {
+            name,
+            option,
+            value,
+            err,
+        }) = &r
+        {
+            assert_eq!(name, "x");
+            assert_eq!(option, "-x");
+            assert_eq!(value, &Some("0q".to_owned()));
+            if let OptionError::InvalidValue(_) = err {
+                return;
             }
         }
         panic!("incorrect result: {:?}", r);
     }
+
+    #[test]
+    fn spelling() {
+        let input = ["--out=f", "-abo", "g", "-c"];
+        let mut args = Args::from(input.iter().map(|&s| s.to_owned())).with_syntax(Syntax::gnu());
+        let mut result = Vec::new();
+        while let Arg::Named(arg) = args.next() {
+            result.push(format!("{} {}", arg.spelling(), arg.raw()));
+            arg.parse(|name, value| match name {
+                "out" | "o" => value.as_str().map(drop),
+                _ => Ok(()),
+            })
+            .unwrap();
+        }
+        assert_eq!(
+            result,
+            ["--out --out=f", "-a -abo", "-b -abo", "-o -abo", "-c -c"]
+        );
+        let mut args = Args::from(vec!["--namee=x".to_owned()].into_iter());
+        if let Arg::Named(arg) = args.next() {
+            let err = arg.parse(|_, _| Err::<(), _>(OptionError::Unknown));
+            assert_eq!(err.unwrap_err().to_string(), "unknown option --namee");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn map_error() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+        let err = UsageError::Multiple(vec![
+            UsageError::UnexpectedArgument {
+                arg: OsString::from_vec(b"a\xffb".to_vec()),
+            },
+            UsageError::MissingArgument {
+                name: "file".to_owned(),
+            },
+        ]);
+        let err: UsageError<String> = err.into();
+        match &err {
+            UsageError::Multiple(errors) => match &errors[0] {
+                UsageError::UnexpectedArgument { arg } => assert_eq!(arg, "a\u{fffd}b"),
+                _ => panic!("incorrect result: {:?}", err),
+            },
+            _ => panic!("incorrect result: {:?}", err),
+        }
+        let err = err.map(|arg| arg.len());
+        assert_eq!(
+            err.to_string(),
+            "unexpected argument 5\nmissing argument <file>"
+        );
+    }
+
+    #[test]
+    fn compare_errors() {
+        use std::error::Error;
+        use std::num::ParseIntError;
+        fn is_send_sync<T: Send + Sync + 'static>(_: &T) -> bool {
+            true
+        }
+        let err = parse_args(&["-x", "0q"]).unwrap_err();
+        assert!(is_send_sync(&err));
+        assert_eq!(
+            err.clone(),
+            UsageError::InvalidOption {
+                name: "x".to_owned(),
+                option: "-x".to_owned(),
+                value: Some("0q".to_owned()),
+                err: "0q".parse::<i32>().unwrap_err().into(),
+            }
+        );
+        assert!(err.source().unwrap().is::<ParseIntError>());
+        let mut args = Args::from(vec!["--flag".to_owned()].into_iter());
+        if let Arg::Named(arg) = args.next() {
+            let err = arg
+                .parse(|_, _| Err::<(), _>(OptionError::custom("not allowed here")))
+                .unwrap_err();
+            assert_eq!(err.to_string(), "option --flag: not allowed here");
+            assert_eq!(err.source().unwrap().to_string(), "not allowed here");
+        }
+    }
+
+    #[test]
+    fn builtin() {
+        let scan = |input: &[&str], order: Order| {
+            let mut args = Args::from(input.iter().map(|&s| s.to_owned()))
+                .with_syntax(Syntax::gnu())
+                .with_order(order)
+                .with_help(vec!["help", "h"])
+                .with_version(vec!["version"])
+                .with_values(vec!["o", "output"])
+                .with_abbreviations(vec!["help", "output", "verbose", "version"]);
+            let r = args.builtin();
+            assert!(args.rest().eq(input.iter().map(|&s| s.to_owned())));
+            r
+        };
+        let any = Order::Intermixed;
+        assert_eq!(scan(&[], any), None);
+        assert_eq!(scan(&["--bogus", "--help"], any), Some(Builtin::Help));
+        assert_eq!(scan(&["x", "-h"], any), Some(Builtin::Help));
+        assert_eq!(scan(&["--version", "-h"], any), Some(Builtin::Version));
+        assert_eq!(scan(&["--help=x", "-xy", "---help"], any), None);
+        assert_eq!(scan(&["-vh"], any), Some(Builtin::Help));
+        assert_eq!(scan(&["-hv"], any), Some(Builtin::Help));
+        assert_eq!(scan(&["--hel"], any), Some(Builtin::Help));
+        assert_eq!(scan(&["--vers"], any), Some(Builtin::Version));
+        assert_eq!(scan(&["--ver"], any), None);
+        assert_eq!(scan(&["--", "--help"], any), None);
+        assert_eq!(
+            scan(&["-v", "--help"], Order::StopAtPositional),
+            Some(Builtin::Help)
+        );
+        assert_eq!(scan(&["x", "--help"], Order::StopAtPositional), None);
+        // Values are skipped, the same way in either order.
+        for &order in &[any, Order::StopAtPositional] {
+            assert_eq!(scan(&["--output", "--help"], order), None);
+            assert_eq!(scan(&["--out", "-h"], order), None);
+            assert_eq!(scan(&["-oh", "-v"], order), None);
+            assert_eq!(scan(&["-vo", "-h"], order), None);
+            assert_eq!(scan(&["-o", "file", "--help"], order), Some(Builtin::Help));
+            assert_eq!(scan(&["--output=x", "--help"], order), Some(Builtin::Help));
+            assert_eq!(scan(&["-ofile", "-h"], order), Some(Builtin::Help));
+        }
+        // Other options are assumed not to take values.
+        assert_eq!(scan(&["-x", "--help"], any), Some(Builtin::Help));
+        assert_eq!(scan(&["-x", "y", "--help"], any), Some(Builtin::Help));
+        assert_eq!(scan(&["-x", "y", "--help"], Order::StopAtPositional), None);
+    }
+
+    #[test]
+    fn passthrough() {
+        let forward = |input: &[&str]| {
+            let mut args = Args::from(input.iter().map(|&s| s.to_owned()))
+                .with_syntax(Syntax::gnu())
+                .with_passthrough_values(vec!["o", "output"]);
+            let mut own = Vec::new();
+            let mut positional = Vec::new();
+            loop {
+                match args.next() {
+                    Arg::Positional(arg, _) => positional.push(arg),
+                    Arg::Named(arg) => {
+                        let r = arg.parse_or_forward(|name, value| match name {
+                            "v" => Ok(name.to_owned()),
+                            "n" => Ok(format!("n={}", value.as_str()?)),
+                            _ => Err(OptionError::Unknown),
+                        });
+                        match r {
+                            Ok(Some(opt)) => own.push(opt),
+                            Ok(None) => (),
+                            Err(err) => own.push(err.to_string()),
+                        }
+                    }
+                    Arg::End => break,
+                    Arg::Error(err) => panic!("error: {}", err),
+                }
+            }
+            (own, args.take_passthrough(), positional)
+        };
+        let (own, passed, positional) = forward(&[
+            "-v",
+            "--color=auto",
+            "--output",
+            "out",
+            "-o",
+            "-v",
+            "--x",
+            "-n",
+            "1",
+            "a",
+            "-ofile",
+            "-qrs",
+            "-vq",
+            "-n2",
+            "--",
+            "-z",
+        ]);
+        assert_eq!(own, ["v", "n=1", "v", "unknown option -q", "n=2"]);
+        assert_eq!(
+            passed,
+            [
+                "--color=auto",
+                "--output",
+                "out",
+                "-o",
+                "-v",
+                "--x",
+                "-ofile",
+                "-qrs"
+            ]
+        );
+        assert_eq!(positional, ["a", "-z"]);
+    }
+
+    #[test]
+    fn input_option() {
+        let path = "/nonexistent/simpleargs-test";
+        let input = [format!("--input={}", path)];
+        let mut args = Args::from(input.iter().cloned()).with_syntax(Syntax::gnu());
+        let mut file = None;
+        if let Arg::Named(arg) = args.next() {
+            arg.parse(|_, value| {
+                file = Some(value.as_input()?);
+                Ok(())
+            })
+            .unwrap();
+        }
+        let err = file.unwrap().open().err().unwrap();
+        let expect = format!("could not open {:?} for option --input: ", path);
+        assert!(err.to_string().starts_with(&expect), "message: {}", err);
+    }
+
+    #[test]
+    fn collect_errors() {
+        let input = ["-x", "0q", "-y", "-flag", "-x", "1", "-flag=2"];
+        let mut args = Args::from(input.iter().map(|&s| s.to_owned()));
+        let mut errors = UsageErrors::new();
+        let mut xvalues = Vec::new();
+        loop {
+            match args.next() {
+                Arg::Positional(arg, _) => errors.push(UsageError::UnexpectedArgument { arg }),
+                Arg::Named(arg) => {
+                    errors.check(arg.parse(|name, value| match name {
+                        "flag" => Ok(()),
+                        "x" => {
+                            xvalues.push(i32::from_str(value.as_str()?)?);
+                            Ok(())
+                        }
+                        _ => Err(OptionError::Unknown),
+                    }));
+                }
+                Arg::End => break,
+                Arg::Error(err) => errors.push(err),
+            }
+        }
+        assert_eq!(xvalues, vec![1]);
+        let err = errors.finish().unwrap_err();
+        match &err {
+            UsageError::Multiple(errors) => assert_eq!(errors.len(), 3),
+            _ => panic!("incorrect result: {:?}", err),
+        }
+        assert_eq!(
+            err.to_string(),
+            "invalid value \"0q\" for option -x: invalid digit found in string\n\
+             unknown option -y\n\
+             option -flag does not accept a parameter",
+        );
+        assert!(UsageErrors::<String>::new().finish().is_ok());
+    }
+
+    #[test]
+    fn attached_value() {
+        let input = ["-ofile1", "-o", "file2", "--o:file3"];
+        let mut args = Args::from(input.iter().map(|&s| s.to_owned()))
+            .with_attached(vec!["o"])
+            .with_separators(":");
+        let result = getopt_args(&mut args, &["o"], |_, x| Some(x));
+        assert_eq!(result.unwrap(), vec!["-o file1", "-o file2", "-o file3"]);
+    }
+
+    /// Parse arguments like getopt, and list the options and operands found. Options in
+    /// `with_value` take a value. Options in `with_value` with a `?` suffix, like `"o?"`, take an
+    /// optional value, which is used if it does not start with `-`.
+    fn getopt(syntax: Syntax, input: &[&str], with_value: &[&str]) -> Result<Vec<String>, String> {
+        let mut args = Args::from(input.iter().map(|&s| s.to_owned())).with_syntax(syntax);
+        getopt_args(&mut args, with_value, |_, x| Some(x))
+    }
+
+    /// Like `getopt`, but parse an existing argument stream. Each operand is passed to `operand`,
+    /// which returns the operand to list, if any.
+    fn getopt_args<T, F>(
+        args: &mut Args<T>,
+        with_value: &[&str],
+        mut operand: F,
+    ) -> Result<Vec<String>, String>
+    where
+        T: Iterator<Item = String>,
+        F: FnMut(&mut Args<T>, String) -> Option<String>,
+    {
+        let mut result = Vec::new();
+        loop {
+            match args.next() {
+                Arg::Positional(x, _) => result.extend(operand(args, x)),
+                Arg::Named(arg) => arg
+                    .parse(|name, mut value| {
+                        let optional = with_value.iter().any(|v| v.strip_suffix('?') == Some(name));
+                        let takes_value = with_value.contains(&name)
+                            || optional && matches!(value.peek(), Some(x) if !x.starts_with('-'));
+                        if takes_value {
+                            result.push(format!("-{} {}", name, value.as_str()?));
+                        } else {
+                            result.push(format!("-{}", name));
+                        }
+                        Ok(())
+                    })
+                    .map_err(|e| e.to_string())?,
+                Arg::End => break,
+                Arg::Error(err) => return Err(err.to_string()),
+            }
+        }
+        Ok(result)
+    }
+
+    fn check_syntax(syntax: Syntax, with_value: &[&str], cases: &[(&[&str], &[&str])]) {
+        let mut success = true;
+        for &(input, expected) in cases.iter() {
+            match getopt(syntax.clone(), input, with_value) {
+                Ok(r) if r == expected => (),
+                r => {
+                    eprintln!("{:?}: got {:?}, expect {:?}", input, r, expected);
+                    success = false;
+                }
+            }
+        }
+        if !success {
+            panic!("failed");
+        }
+    }
+
+    fn check_syntax_errors(syntax: Syntax, with_value: &[&str], cases: &[&[&str]]) {
+        let mut success = true;
+        for &input in cases.iter() {
+            if let Ok(r) = getopt(syntax.clone(), input, with_value) {
+                eprintln!("{:?}: got {:?}, expect error", input, r);
+                success = false;
+            }
+        }
+        if !success {
+            panic!("failed");
+        }
+    }
+
+    /// Equivalent command lines from the POSIX getopt() examples, with optstring ":abf:o:".
+    const POSIX_EXAMPLES: &[&[&str]] = &[
+        &["-ao", "arg", "path", "path"],
+        &["-a", "-o", "arg", "path", "path"],
+        &["-o", "arg", "-a", "path", "path"],
+        &["-a", "-o", "arg", "--", "path", "path"],
+        &["-a", "-oarg", "path", "path"],
+        &["-aoarg", "path", "path"],
+    ];
+
+    fn check_posix_examples(syntax: Syntax) {
+        for &input in POSIX_EXAMPLES.iter() {
+            let mut r = getopt(syntax.clone(), input, &["f", "o"]).unwrap();
+            // The examples are equivalent, but options are seen in different orders.
+            r.sort();
+            assert_eq!(r, ["-a", "-o arg", "path", "path"], "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn syntax_posix() {
+        check_posix_examples(Syntax::posix());
+        check_syntax(
+            Syntax::posix(),
+            &["f", "o"],
+            &[
+                (&["-", "-b"], &["-", "-b"]),
+                (&["-ba", "--", "-a"], &["-b", "-a", "-a"]),
+                (&["-f-a"], &["-f -a"]),
+                (&["-f", "-a"], &["-f -a"]),
+            ],
+        );
+        check_syntax_errors(
+            Syntax::posix(),
+            &["f", "o"],
+            &[&["--all"], &["-a-"], &["-o"], &["-b+"]],
+        );
+    }
+
+    #[test]
+    fn syntax_gnu() {
+        check_posix_examples(Syntax::gnu());
+        let cases: &[(&[&str], &[&str])] = &[
+            (&["--all"], &["-all"]),
+            (
+                &["--output=x", "--output", "y"],
+                &["-output x", "-output y"],
+            ),
+            (&["--output="], &["-output "]),
+            (&["-a", "path", "-b"], &["-a", "path", "-b"]),
+            (&["-", "--", "--all"], &["-", "--all"]),
+        ];
+        check_syntax(Syntax::gnu(), &["f", "o", "output"], cases);
+        check_syntax_errors(
+            Syntax::gnu(),
+            &["f", "o", "output"],
+            &[&["--all=x"], &["---all"], &["-ab="]],
+        );
+        // The whole group is reported, not just the invalid part.
+        assert_eq!(
+            getopt(Syntax::gnu(), &["-a+"], &[]),
+            Err(r#"invalid argument "-a+""#.to_owned())
+        );
+    }
+
+    #[test]
+    fn syntax_go() {
+        check_syntax(
+            Syntax::go(),
+            &["n"],
+            &[
+                (&["-flag", "--flag"], &["-flag", "-flag"]),
+                (&["-n=1", "--n=2", "-n", "3"], &["-n 1", "-n 2", "-n 3"]),
+                (&["-x.y"], &["-x.y"]),
+                // The first positional argument ends option processing.
+                (&["-", "--", "-flag"], &["-", "--", "-flag"]),
+                (&["-a", "x", "-n", "1"], &["-a", "x", "-n", "1"]),
+                (&["-a-", "--", "-b"], &["-a-", "-b"]),
+            ],
+        );
+        check_syntax_errors(Syntax::go(), &["n"], &[&["---flag"], &["-=x"]]);
+    }
+
+    #[test]
+    fn syntax_windows() {
+        check_syntax(
+            Syntax::windows(),
+            &["out"],
+            &[
+                (&["/a", "/out:x", "/out", "y"], &["-a", "-out x", "-out y"]),
+                (&["/?"], &["-?"]),
+                (&["-a", "--", "/"], &["-a", "--", "/"]),
+            ],
+        );
+        check_syntax_errors(Syntax::windows(), &["out"], &[&["/a:b"], &["/a=b"]]);
+    }
+
+    #[test]
+    fn abbreviations() {
+        let parse = |input: &[&str]| {
+            let names = ["verbose", "version", "v", "output"];
+            let mut args = Args::from(input.iter().map(|&s| s.to_owned()))
+                .with_syntax(Syntax::gnu())
+                .with_abbreviations(names.iter().cloned());
+            let mut result = Vec::new();
+            loop {
+                match args.next() {
+                    Arg::Named(arg) => arg.parse(|name, _| {
+                        result.push(name.to_owned());
+                        Ok(())
+                    })?,
+                    Arg::End => break,
+                    _ => panic!("unexpected argument"),
+                }
+            }
+            Ok(result)
+        };
+        assert_eq!(
+            parse(&["--verb", "--vers", "--v", "--out", "-o", "--x"]).unwrap(),
+            vec!["verbose", "version", "v", "output", "o", "x"],
+        );
+        match parse(&["--ver"]) {
+            Err(UsageError::InvalidOption {
+                name,
+                err: OptionError::Ambiguous(names),
+                ..
+            }) => {
+                assert_eq!(name, "ver");
+                assert_eq!(names, vec!["verbose", "version"]);
+            }
+            r => panic!("incorrect result: {:?}", r),
+        }
+        assert_eq!(
+            parse(&["--ver"]).unwrap_err().to_string(),
+            "ambiguous option --ver, could be --verbose, --version",
+        );
+    }
+
+    #[test]
+    fn constraints() {
+        let mut args = Args::from(["--verb", "-q", "-x"].iter().map(|&s| s.to_owned()))
+            .with_syntax(Syntax::gnu())
+            .with_abbreviations(vec!["verbose"]);
+        while let Arg::Named(arg) = args.next() {
+            let _ = arg.parse(|name, _| match name {
+                "x" => Err(OptionError::Unknown),
+                _ => Ok(()),
+            });
+        }
+        assert!(args
+            .check(&Constraints::new().conflicts("x", "verbose"))
+            .is_ok());
+        assert_eq!(
+            args.check(&Constraints::new().conflicts("q", "verbose"))
+                .unwrap_err()
+                .to_string(),
+            "option -q cannot be used with --verb",
+        );
+        // Required options are written with a prefix which is valid for them.
+        let requires = |a, b| {
+            args.check(&Constraints::new().requires(a, b))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(requires("q", "cert"), "option -q requires --cert");
+        assert_eq!(requires("verbose", "k"), "option --verb requires -k");
+    }
+
+    #[test]
+    fn order() {
+        let input = ["-a", "run", "-b", "cmd", "-x", "--", "y"];
+        let mut args = Args::from(input.iter().map(|&s| s.to_owned()));
+        let result = getopt_args(&mut args, &[], |args, x| {
+            if x == "run" {
+                args.set_order(Order::StopAtPositional);
+            }
+            Some(x)
+        });
+        assert_eq!(
+            result.unwrap(),
+            vec!["-a", "run", "-b", "cmd", "-x", "--", "y"]
+        );
+    }
+
+    #[test]
+    fn syntax_posix_order() {
+        // The POSIX profile stops at the first operand by itself.
+        let input = ["-a", "path", "-b", "x"];
+        let result = getopt(Syntax::posix(), &input, &["b"]);
+        assert_eq!(result.unwrap(), vec!["-a", "path", "-b", "x"]);
+        // An explicit order overrides the profile.
+        let mut args = Args::from(input.iter().map(|&s| s.to_owned()))
+            .with_syntax(Syntax::posix())
+            .with_order(Order::Intermixed);
+        let result = getopt_args(&mut args, &["b"], |_, x| Some(x));
+        assert_eq!(result.unwrap(), vec!["-a", "path", "-b x"]);
+    }
+
+    #[test]
+    fn separator() {
+        let input = ["-v", "a", "--", "-x", "--", "b"];
+        let mut args = Args::from(input.iter().map(|&s| s.to_owned()));
+        let mut forward = Vec::new();
+        let own = getopt_args(&mut args, &[], |args, x| {
+            if !args.after_separator() {
+                return Some(x);
+            }
+            forward.extend(args.take_separator());
+            forward.push(x);
+            None
+        });
+        assert_eq!(own.unwrap(), vec!["-v", "a"]);
+        assert_eq!(forward, vec!["--", "-x", "--", "b"]);
+        assert!(!args.options_enabled());
+    }
+
+    #[test]
+    fn reenable_options() {
+        let input = ["--", "sub", "-x", "--", "y"];
+        let mut args = Args::from(input.iter().map(|&s| s.to_owned()));
+        let result = getopt_args(&mut args, &[], |args, x| {
+            if x == "sub" {
+                args.set_options_enabled(true);
+            }
+            Some(x)
+        });
+        assert_eq!(result.unwrap(), vec!["sub", "-x", "y"]);
+    }
+
+    #[test]
+    fn peek_value() {
+        // -o takes an optional value.
+        let result = getopt(
+            Syntax::new(),
+            &["-o", "-v", "-o", "x", "-o=z", "y"],
+            &["o?"],
+        );
+        assert_eq!(result.unwrap(), vec!["-o", "-v", "-o x", "-o z", "y"]);
+    }
+
+    #[test]
+    fn unread() {
+        let input = ["a", "b", "c"];
+        let mut args = Args::from(input.iter().map(|&s| s.to_owned()));
+        assert_eq!(args.peek().map(String::as_str), Some("a"));
+        match args.next() {
+            Arg::Positional(x, _) => {
+                assert_eq!(x, "a");
+                args.unread(x);
+            }
+            _ => panic!("expected positional"),
+        }
+        args.unread("-x".to_owned());
+        match args.next() {
+            Arg::Named(arg) => arg
+                .parse(|name, value| {
+                    assert_eq!(name, "x");
+                    assert_eq!(value.as_str()?, "a");
+                    Ok(())
+                })
+                .unwrap(),
+            _ => panic!("expected named"),
+        }
+        assert_eq!(args.peek().map(String::as_str), Some("b"));
+        assert_eq!(args.rest().collect::<Vec<_>>(), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn unread_group() {
+        let input = ["-ab", "c"];
+        let mut args = Args::from(input.iter().map(|&s| s.to_owned())).with_syntax(Syntax::gnu());
+        match args.next() {
+            Arg::Named(arg) => arg.parse(|_, _| Ok(())).unwrap(),
+            _ => panic!("expected named"),
+        }
+        args.unread("-q".to_owned());
+        assert_eq!(args.peek().map(String::as_str), Some("-q"));
+        // The rest of the group comes first.
+        let result = getopt_args(&mut args, &[], |_, x| Some(x));
+        assert_eq!(result.unwrap(), vec!["-b", "-q", "c"]);
+    }
+
+    #[test]
+    fn reject_option_values() {
+        let parse = |input: &[&str]| {
+            let mut args = Args::from(input.iter().map(|&s| s.to_owned()))
+                .with_value_policy(ValuePolicy::RejectOptions);
+            let mut result = Vec::new();
+            loop {
+                match args.next() {
+                    Arg::Positional(x, _) => result.push(x),
+                    Arg::Named(arg) => arg.parse(|name, value| {
+                        match name {
+                            "n" | "output" => result.push(value.as_str()?.to_owned()),
+                            "force" => {
+                                result.push(value.policy(ValuePolicy::Any).as_str()?.to_owned())
+                            }
+                            _ => result.push(format!("-{}", name)),
+                        }
+                        Ok(())
+                    })?,
+                    Arg::End => break,
+                    Arg::Error(err) => return Err(err),
+                }
+            }
+            Ok(result)
+        };
+        assert_eq!(
+            parse(&[
+                "--output",
+                "-",
+                "-n",
+                "-5",
+                "-n",
+                "-.5e3",
+                "--output=--verbose"
+            ])
+            .unwrap(),
+            vec!["-", "-5", "-.5e3", "--verbose"],
+        );
+        assert_eq!(parse(&["--force", "--x"]).unwrap(), vec!["--x"]);
+        for &input in [
+            &["--output", "--verbose"][..],
+            &["--output", "-v"],
+            &["-n", "--"],
+        ]
+        .iter()
+        {
+            match parse(input) {
+                Err(UsageError::InvalidOption {
+                    value: None,
+                    err: OptionError::OptionAsParameter(arg),
+                    ..
+                }) => assert_eq!(arg, input[1]),
+                r => panic!("{:?}: incorrect result: {:?}", input, r),
+            }
+        }
+        assert_eq!(
+            parse(&["--output", "--verbose"]).unwrap_err().to_string(),
+            "option --output requires a parameter; got option --verbose",
+        );
+    }
 }
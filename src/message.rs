@@ -0,0 +1,497 @@
+//! Error message text.
+
+use crate::shell;
+use crate::{ArgString, UsageError};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// The text of a usage error message, identified by kind, with its parameters.
+///
+/// Option names are given without leading dashes, and options as typed by the user include them.
+/// Argument values are formatted with `{:?}`.
+#[derive(Debug, Clone, Copy)]
+pub enum Message<'a> {
+    /// See [`UsageError::InvalidArgument`].
+    InvalidArgument {
+        /// Full text of the argument.
+        arg: &'a dyn Debug,
+    },
+    /// See [`UsageError::UnexpectedArgument`].
+    UnexpectedArgument {
+        /// Full text of the argument.
+        arg: &'a dyn Debug,
+    },
+    /// See [`UsageError::MissingArgument`].
+    MissingArgument {
+        /// The name of the argument.
+        name: &'a str,
+    },
+    /// See [`OptionError::Unknown`](crate::OptionError::Unknown).
+    UnknownOption {
+        /// The name of the option.
+        name: &'a str,
+        /// The option as the user typed it, like `--name`.
+        option: &'a str,
+    },
+    /// See [`OptionError::Ambiguous`](crate::OptionError::Ambiguous).
+    AmbiguousOption {
+        /// The name of the option.
+        name: &'a str,
+        /// The option as the user typed it, like `--name`.
+        option: &'a str,
+        /// The names of the matching options.
+        candidates: &'a [String],
+    },
+    /// See [`OptionError::MissingParameter`](crate::OptionError::MissingParameter).
+    MissingParameter {
+        /// The name of the option.
+        name: &'a str,
+        /// The option as the user typed it, like `--name`.
+        option: &'a str,
+    },
+    /// See [`OptionError::OptionAsParameter`](crate::OptionError::OptionAsParameter).
+    OptionAsParameter {
+        /// The name of the option.
+        name: &'a str,
+        /// The option as the user typed it, like `--name`.
+        option: &'a str,
+        /// The next argument, which looks like an option.
+        arg: &'a str,
+    },
+    /// See [`OptionError::UnexpectedParameter`](crate::OptionError::UnexpectedParameter).
+    UnexpectedParameter {
+        /// The name of the option.
+        name: &'a str,
+        /// The option as the user typed it, like `--name`.
+        option: &'a str,
+    },
+    /// See [`OptionError::InvalidUnicode`](crate::OptionError::InvalidUnicode).
+    InvalidUnicode {
+        /// The name of the option.
+        name: &'a str,
+        /// The option as the user typed it, like `--name`.
+        option: &'a str,
+        /// The option parameter value.
+        value: &'a dyn Debug,
+    },
+    /// See [`OptionError::InvalidChoice`](crate::OptionError::InvalidChoice).
+    InvalidChoice {
+        /// The name of the option.
+        name: &'a str,
+        /// The option as the user typed it, like `--name`.
+        option: &'a str,
+        /// The option parameter value.
+        value: &'a dyn Debug,
+        /// The accepted choices.
+        choices: &'a [String],
+    },
+    /// See [`OptionError::InvalidValue`](crate::OptionError::InvalidValue).
+    InvalidValue {
+        /// The name of the option.
+        name: &'a str,
+        /// The option as the user typed it, like `--name`.
+        option: &'a str,
+        /// The option parameter value.
+        value: &'a dyn Debug,
+        /// The error from parsing the value.
+        err: &'a dyn Error,
+    },
+    /// See [`OptionError::InvalidValue`](crate::OptionError::InvalidValue), for an option
+    /// without a value.
+    OptionFailed {
+        /// The name of the option.
+        name: &'a str,
+        /// The option as the user typed it, like `--name`.
+        option: &'a str,
+        /// The error from the option.
+        err: &'a dyn Error,
+    },
+    /// See [`OptionError::InvalidChoice`](crate::OptionError::InvalidChoice), for an option
+    /// without a value.
+    ChoiceFailed {
+        /// The name of the option.
+        name: &'a str,
+        /// The option as the user typed it, like `--name`.
+        option: &'a str,
+        /// The accepted choices.
+        choices: &'a [String],
+    },
+    /// See [`UsageError::MissingOption`].
+    MissingOption {
+        /// The option, written with a prefix which is valid for it.
+        option: &'a str,
+    },
+    /// See [`UsageError::ConflictingOptions`].
+    ConflictingOptions {
+        /// The option which appeared first, as typed by the user.
+        first: &'a str,
+        /// The option which appeared second, as typed by the user.
+        second: &'a str,
+    },
+    /// See [`UsageError::OptionRequires`].
+    OptionRequires {
+        /// The option which was used, as typed by the user.
+        name: &'a str,
+        /// The option which is required, written with a prefix which is valid for it.
+        required: &'a str,
+    },
+    /// See [`UsageError::MissingOneOf`].
+    MissingOneOf {
+        /// The options in the group, written with a prefix which is valid for each.
+        options: &'a [String],
+    },
+}
+
+impl<'a> Message<'a> {
+    /// Replace the argument or option value in the message.
+    pub(crate) fn with_value<'b>(self, value: &'b dyn Debug) -> Message<'b>
+    where
+        'a: 'b,
+    {
+        match self {
+            Message::InvalidArgument { .. } => Message::InvalidArgument { arg: value },
+            Message::UnexpectedArgument { .. } => Message::UnexpectedArgument { arg: value },
+            Message::InvalidUnicode { name, option, .. } => Message::InvalidUnicode {
+                name,
+                option,
+                value,
+            },
+            Message::InvalidChoice {
+                name,
+                option,
+                choices,
+                ..
+            } => Message::InvalidChoice {
+                name,
+                option,
+                value,
+                choices,
+            },
+            Message::InvalidValue {
+                name, option, err, ..
+            } => Message::InvalidValue {
+                name,
+                option,
+                value,
+                err,
+            },
+            message => message,
+        }
+    }
+
+    /// Get a stable identifier for the kind of message, like `"unknown-option"`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Message::InvalidArgument { .. } => "invalid-argument",
+            Message::UnexpectedArgument { .. } => "unexpected-argument",
+            Message::MissingArgument { .. } => "missing-argument",
+            Message::UnknownOption { .. } => "unknown-option",
+            Message::AmbiguousOption { .. } => "ambiguous-option",
+            Message::MissingParameter { .. } => "missing-parameter",
+            Message::OptionAsParameter { .. } => "option-as-parameter",
+            Message::UnexpectedParameter { .. } => "unexpected-parameter",
+            Message::InvalidUnicode { .. } => "invalid-unicode",
+            Message::InvalidChoice { .. } => "invalid-choice",
+            Message::InvalidValue { .. } => "invalid-value",
+            Message::OptionFailed { .. } => "option-failed",
+            Message::ChoiceFailed { .. } => "choice-failed",
+            Message::MissingOption { .. } => "missing-option",
+            Message::ConflictingOptions { .. } => "conflicting-options",
+            Message::OptionRequires { .. } => "option-requires",
+            Message::MissingOneOf { .. } => "missing-one-of",
+        }
+    }
+}
+
+/// A catalog of translated error messages.
+///
+/// ```
+/// use simpleargs::{Catalog, English, Message, OptionError, UsageError};
+/// use std::fmt::{Formatter, Result};
+/// struct German;
+/// impl Catalog for German {
+///     fn format(&self, message: &Message, f: &mut Formatter) -> Result {
+///         match message {
+///             Message::UnknownOption { option, .. } => write!(f, "unbekannte Option {}", option),
+///             _ => English.format(message, f),
+///         }
+///     }
+/// }
+/// let err = UsageError::<String>::InvalidOption {
+///     name: "x".to_owned(),
+///     option: "-x".to_owned(),
+///     value: None,
+///     err: OptionError::Unknown,
+/// };
+/// assert_eq!(err.display(&German).to_string(), "unbekannte Option -x");
+/// let err = UsageError::<String>::MissingOption { option: "-x".to_owned() };
+/// assert_eq!(err.display(&German).to_string(), "missing required option -x");
+/// ```
+pub trait Catalog {
+    /// Write the text for a message.
+    fn format(&self, message: &Message, f: &mut Formatter) -> FmtResult;
+}
+
+/// The default catalog, with messages in English.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct English;
+
+/// Write a list of option names with a prefix, like "-a, -b, -c".
+fn write_options(f: &mut Formatter, prefix: &str, names: &[String]) -> FmtResult {
+    for (i, name) in names.iter().enumerate() {
+        write!(f, "{}{}{}", if i == 0 { "" } else { ", " }, prefix, name)?;
+    }
+    Ok(())
+}
+
+impl Catalog for English {
+    fn format(&self, message: &Message, f: &mut Formatter) -> FmtResult {
+        match *message {
+            Message::InvalidArgument { arg } => write!(f, "invalid argument {:?}", arg),
+            Message::UnexpectedArgument { arg } => write!(f, "unexpected argument {:?}", arg),
+            Message::MissingArgument { name } => write!(f, "missing argument <{}>", name),
+            Message::UnknownOption { option, .. } => write!(f, "unknown option {}", option),
+            Message::AmbiguousOption {
+                name,
+                option,
+                candidates,
+            } => {
+                // The user typed the name, so the rest is the prefix.
+                let prefix = option.strip_suffix(name).unwrap_or("-");
+                write!(f, "ambiguous option {}, could be ", option)?;
+                write_options(f, prefix, candidates)
+            }
+            Message::MissingParameter { option, .. } => {
+                write!(f, "option {} requires a parameter", option)
+            }
+            Message::OptionAsParameter { option, arg, .. } => write!(
+                f,
+                "option {} requires a parameter; got option {}",
+                option, arg
+            ),
+            Message::UnexpectedParameter { option, .. } => {
+                write!(f, "option {} does not accept a parameter", option)
+            }
+            Message::InvalidUnicode { option, value, .. } => write!(
+                f,
+                "invalid value {:?} for option {}: invalid Unicode string",
+                value, option
+            ),
+            Message::InvalidChoice {
+                option,
+                value,
+                choices,
+                ..
+            } => write!(
+                f,
+                "invalid value {:?} for option {}: expected one of {}",
+                value,
+                option,
+                choices.join(", ")
+            ),
+            Message::InvalidValue {
+                option, value, err, ..
+            } => write!(
+                f,
+                "invalid value {:?} for option {}: {}",
+                value, option, err
+            ),
+            Message::OptionFailed { option, err, .. } => write!(f, "option {}: {}", option, err),
+            Message::ChoiceFailed {
+                option, choices, ..
+            } => write!(
+                f,
+                "option {}: expected one of {}",
+                option,
+                choices.join(", ")
+            ),
+            Message::MissingOption { option } => write!(f, "missing required option {}", option),
+            Message::ConflictingOptions { first, second } => {
+                write!(f, "option {} cannot be used with {}", second, first)
+            }
+            Message::OptionRequires { name, required } => {
+                write!(f, "option {} requires {}", name, required)
+            }
+            Message::MissingOneOf { options } => {
+                write!(f, "one of the options ")?;
+                write_options(f, "", options)?;
+                write!(f, " is required")
+            }
+        }
+    }
+}
+
+/// A usage error displayed using a message catalog. Returned by [`UsageError::display`].
+pub struct Localized<'a, T, C: ?Sized> {
+    pub(crate) err: &'a UsageError<T>,
+    pub(crate) catalog: &'a C,
+    pub(crate) quote: Option<fn(&T) -> String>,
+}
+
+impl<'a, T, C: ?Sized> Localized<'a, T, C>
+where
+    T: ArgString,
+{
+    /// Display argument values quoted for a POSIX shell, instead of with `{:?}`. This way, the
+    /// values can be pasted back into a shell to reproduce the error.
+    ///
+    /// ```
+    /// use simpleargs::{English, UsageError};
+    /// let err = UsageError::UnexpectedArgument {
+    ///     arg: "two words".to_owned(),
+    /// };
+    /// assert_eq!(
+    ///     err.display(&English).quoted().to_string(),
+    ///     "unexpected argument 'two words'",
+    /// );
+    /// ```
+    pub fn quoted(self) -> Self {
+        Localized {
+            quote: Some(|value| shell::quote(value)),
+            ..self
+        }
+    }
+}
+
+/// A value which is formatted as-is with `{:?}`.
+struct Verbatim(String);
+
+impl Debug for Verbatim {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'a, T, C> Display for Localized<'a, T, C>
+where
+    T: Debug,
+    C: Catalog + ?Sized,
+{
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self.err.message() {
+            Some(message) => match (self.quote, self.err.value()) {
+                (Some(quote), Some(value)) => {
+                    let value = Verbatim(quote(value));
+                    self.catalog.format(&message.with_value(&value), f)
+                }
+                _ => self.catalog.format(&message, f),
+            },
+            None => {
+                if let UsageError::Multiple(errors) = self.err {
+                    for (i, err) in errors.iter().enumerate() {
+                        if i != 0 {
+                            writeln!(f)?;
+                        }
+                        let err = Localized {
+                            err,
+                            catalog: self.catalog,
+                            quote: self.quote,
+                        };
+                        Display::fmt(&err, f)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::OptionError;
+
+    /// A catalog which writes the message ID and parameters.
+    struct Test;
+
+    impl Catalog for Test {
+        fn format(&self, message: &Message, f: &mut Formatter) -> FmtResult {
+            write!(f, "{}", message.id())?;
+            match *message {
+                Message::UnknownOption { name, .. } => write!(f, "({})", name),
+                Message::InvalidValue { name, value, .. } => write!(f, "({}, {:?})", name, value),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn catalog() {
+        let err = UsageError::Multiple(vec![
+            UsageError::InvalidOption {
+                name: "x".to_owned(),
+                option: "--x".to_owned(),
+                value: None,
+                err: OptionError::Unknown,
+            },
+            UsageError::InvalidOption {
+                name: "n".to_owned(),
+                option: "-n".to_owned(),
+                value: Some("q".to_owned()),
+                err: "q".parse::<i32>().unwrap_err().into(),
+            },
+            UsageError::MissingOneOf {
+                options: vec!["-a".to_owned(), "-b".to_owned()],
+            },
+        ]);
+        assert_eq!(
+            err.display(&Test).to_string(),
+            "unknown-option(x)\ninvalid-value(n, \"q\")\nmissing-one-of"
+        );
+        assert_eq!(
+            err.to_string(),
+            "unknown option --x\n\
+             invalid value \"q\" for option -n: invalid digit found in string\n\
+             one of the options -a, -b is required"
+        );
+    }
+
+    #[test]
+    fn without_value() {
+        let error = |err| UsageError::<String>::InvalidOption {
+            name: "format".to_owned(),
+            option: "--format".to_owned(),
+            value: None,
+            err,
+        };
+        let err = error(OptionError::InvalidChoice(vec![
+            "json".to_owned(),
+            "yaml".to_owned(),
+        ]));
+        assert_eq!(err.message().unwrap().id(), "choice-failed");
+        assert_eq!(
+            err.to_string(),
+            "option --format: expected one of json, yaml"
+        );
+        let err = error(OptionError::InvalidUnicode);
+        assert_eq!(err.message().unwrap().id(), "option-failed");
+        assert_eq!(err.to_string(), "option --format: invalid Unicode string");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quoted() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+        let err = UsageError::Multiple(vec![
+            UsageError::InvalidOption {
+                name: "name".to_owned(),
+                option: "-name".to_owned(),
+                value: Some(OsString::from_vec(b"\xff".to_vec())),
+                err: OptionError::InvalidUnicode,
+            },
+            UsageError::UnexpectedArgument {
+                arg: OsString::from("a'b"),
+            },
+            UsageError::MissingOption {
+                option: "-x".to_owned(),
+            },
+        ]);
+        assert_eq!(
+            err.quoted().to_string(),
+            "invalid value $'\\xff' for option -name: invalid Unicode string\n\
+             unexpected argument $'a\\'b'\n\
+             missing required option -x"
+        );
+    }
+}
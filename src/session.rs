@@ -0,0 +1,333 @@
+//! Parsing and completion for interactive input.
+
+use crate::arg::Syntax;
+use crate::shell::{self, SplitError};
+use crate::{Args, Choices, UsageError};
+use std::error::Error;
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::sync::Arc;
+use std::vec;
+
+/// An error parsing a line of input in a [`Session`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineError {
+    /// The line could not be split into arguments.
+    Split(SplitError),
+    /// The arguments were invalid.
+    Usage(UsageError<OsString>),
+}
+
+impl Display for LineError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            LineError::Split(err) => Display::fmt(err, f),
+            LineError::Usage(err) => Display::fmt(&err.quoted(), f),
+        }
+    }
+}
+
+impl Error for LineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LineError::Split(err) => Some(err),
+            LineError::Usage(err) => Some(err),
+        }
+    }
+}
+
+impl From<SplitError> for LineError {
+    fn from(err: SplitError) -> Self {
+        LineError::Split(err)
+    }
+}
+
+impl From<UsageError<OsString>> for LineError {
+    fn from(err: UsageError<OsString>) -> Self {
+        LineError::Usage(err)
+    }
+}
+
+/// Completion candidates for a partial line. Returned by [`Session::complete`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// The byte offset in the line where the word being completed starts.
+    pub start: usize,
+    /// Replacements for the word being completed, quoted for the shell.
+    pub candidates: Vec<String>,
+}
+
+/// An option known to a session.
+#[derive(Debug, Clone)]
+struct OptionInfo {
+    name: String,
+    choices: Option<Vec<String>>,
+}
+
+/// A parser for interactive input, which parses each line with the same syntax and can complete
+/// partial lines.
+///
+/// Lines are split like a POSIX shell, with [`shell::split`]. Each option is listed once in the
+/// session, and the list is used both to complete option names and, when parsing, as the names
+/// which may be abbreviated with [`Args::with_abbreviations`]. The callback passed to
+/// [`Session::parse`] gives the options their meaning, and always sees the full names.
+///
+/// ```
+/// use simpleargs::{Arg, Choices, OptionError, Session, Syntax};
+/// let formats = Choices::new(&[("json", 0), ("yaml", 1)]);
+/// let session = Session::new(Syntax::gnu())
+///     .option("verbose")
+///     .option("version")
+///     .option_choices("format", &formats);
+///
+/// let verbose = session.parse("--verb", |mut args| {
+///     let mut verbose = false;
+///     while let Arg::Named(arg) = args.next() {
+///         arg.parse(|name, _| match name {
+///             "verbose" => {
+///                 verbose = true;
+///                 Ok(())
+///             }
+///             _ => Err(OptionError::Unknown),
+///         })?;
+///     }
+///     Ok(verbose)
+/// });
+/// assert_eq!(verbose, Ok(true));
+///
+/// let completion = session.complete("show --ver");
+/// assert_eq!(completion.start, 5);
+/// assert_eq!(completion.candidates, ["--verbose", "--version"]);
+/// assert_eq!(session.complete("show --format=y").candidates, ["--format=yaml"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session {
+    syntax: Arc<Syntax>,
+    options: Vec<OptionInfo>,
+}
+
+impl Session {
+    /// Create a session which parses lines with the given syntax.
+    pub fn new(syntax: Syntax) -> Self {
+        Session {
+            syntax: Arc::new(syntax),
+            options: Vec::new(),
+        }
+    }
+
+    /// Add an option name, for completion.
+    pub fn option(mut self, name: &str) -> Self {
+        self.options.push(OptionInfo {
+            name: name.to_owned(),
+            choices: None,
+        });
+        self
+    }
+
+    /// Add an option name which takes one of a set of choices as its value, for completion.
+    pub fn option_choices<C>(mut self, name: &str, choices: &Choices<'_, C>) -> Self {
+        self.options.push(OptionInfo {
+            name: name.to_owned(),
+            choices: Some(choices.names().map(str::to_owned).collect()),
+        });
+        self
+    }
+
+    /// Split a line into an argument stream. The stream shares the session's syntax, and accepts
+    /// abbreviations of the session's options.
+    pub fn args(&self, line: &str) -> Result<Args<vec::IntoIter<OsString>>, SplitError> {
+        let args = shell::split(line)?;
+        Ok(Args::from(args.into_iter())
+            .with_syntax(Arc::clone(&self.syntax))
+            .with_abbreviations(self.options.iter().map(|option| option.name.as_str())))
+    }
+
+    /// Split a line into arguments and parse them with a callback.
+    pub fn parse<U, F>(&self, line: &str, f: F) -> Result<U, LineError>
+    where
+        F: FnOnce(Args<vec::IntoIter<OsString>>) -> Result<U, UsageError<OsString>>,
+    {
+        Ok(f(self.args(line)?)?)
+    }
+
+    /// Get completion candidates for the last word in a partial line.
+    ///
+    /// Option names are completed, as are the values of options with choices, whether the value
+    /// is attached like `--format=json` or separate like `--format json`. If the line ends with
+    /// whitespace, an empty word at the end is completed. If the line cannot be split, for
+    /// example because it ends inside quotes, there are no candidates.
+    pub fn complete(&self, line: &str) -> Completion {
+        let mut completion = Completion {
+            start: line.len(),
+            candidates: Vec::new(),
+        };
+        let mut words = match shell::split_bytes(line) {
+            Ok(words) => words,
+            Err(_) => return completion,
+        };
+        // The last word is being typed if nothing follows it, not even whitespace.
+        let word = match words.last() {
+            Some((range, _)) if range.end == line.len() => {
+                let (range, word) = words.pop().unwrap();
+                completion.start = range.start;
+                match String::from_utf8(word) {
+                    Ok(word) => word,
+                    Err(_) => return completion,
+                }
+            }
+            _ => String::new(),
+        };
+        let words: Vec<String> = words
+            .into_iter()
+            .map(|(_, word)| String::from_utf8_lossy(&word).into_owned())
+            .collect();
+        if words.iter().any(|word| self.syntax.is_end_marker(word)) {
+            return completion;
+        }
+        let mut candidates = Vec::new();
+        if let Some(partial) = self.syntax.split_partial(&word) {
+            match partial.value {
+                None => {
+                    for option in self.options.iter() {
+                        if option.name.starts_with(partial.name)
+                            && (!partial.short || option.name.chars().count() == 1)
+                        {
+                            candidates.push(format!("{}{}", partial.prefix, option.name));
+                        }
+                    }
+                }
+                Some(value) => {
+                    // Keep the option and separator as typed.
+                    let head = &word[..word.len() - value.len()];
+                    for choice in self.choices(partial.name, value) {
+                        candidates.push(format!("{}{}", head, choice));
+                    }
+                }
+            }
+        } else if let Some(last) = words.last() {
+            if let Some(option) = self.syntax.split_partial(last) {
+                if option.value.is_none() {
+                    candidates.extend(self.choices(option.name, &word).map(str::to_owned));
+                }
+            }
+        }
+        completion.candidates = candidates.iter().map(shell::quote).collect();
+        completion
+    }
+
+    /// Get the choices for an option which start with a prefix.
+    fn choices<'a>(&'a self, name: &'a str, prefix: &'a str) -> impl Iterator<Item = &'a str> {
+        self.options
+            .iter()
+            .filter(move |option| option.name == name)
+            .filter_map(|option| option.choices.as_ref())
+            .flatten()
+            .map(String::as_str)
+            .filter(move |choice| choice.starts_with(prefix))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Arg, OptionError};
+
+    fn session<'a>(formats: &Choices<'a, i32>) -> Session {
+        Session::new(Syntax::gnu())
+            .option("all")
+            .option("a")
+            .option("b")
+            .option_choices("format", formats)
+    }
+
+    fn candidates(session: &Session, line: &str) -> Vec<String> {
+        session.complete(line).candidates
+    }
+
+    #[test]
+    fn complete_options() {
+        let formats = Choices::new(&[("json", 0), ("yaml", 1), ("yml", 2)]);
+        let session = session(&formats);
+        assert_eq!(
+            candidates(&session, "cmd --"),
+            ["--all", "--a", "--b", "--format"]
+        );
+        assert_eq!(candidates(&session, "cmd -"), ["-a", "-b"]);
+        assert_eq!(candidates(&session, "cmd --f"), ["--format"]);
+        assert_eq!(
+            candidates(&session, "cmd --format=y"),
+            ["--format=yaml", "--format=yml"]
+        );
+        assert_eq!(
+            candidates(&session, "cmd --format "),
+            ["json", "yaml", "yml"]
+        );
+        assert_eq!(candidates(&session, "cmd --format j"), ["json"]);
+        assert_eq!(candidates(&session, "cmd --all "), Vec::<String>::new());
+        assert_eq!(candidates(&session, "cmd -- --"), Vec::<String>::new());
+        assert_eq!(candidates(&session, "cmd '--f"), Vec::<String>::new());
+        let completion = session.complete("cmd 'x' --a");
+        assert_eq!(completion.start, 8);
+        assert_eq!(completion.candidates, ["--all", "--a"]);
+        assert_eq!(session.complete("cmd ").start, 4);
+        // An escaped space does not end the word.
+        let completion = session.complete(r"cmd --al\ ");
+        assert_eq!(completion.start, 4);
+        assert_eq!(completion.candidates, Vec::<String>::new());
+        assert_eq!(
+            candidates(&session, r"cmd --format\ "),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn parse_lines() {
+        let session = Session::new(Syntax::gnu());
+        let count = |line| {
+            session.parse(line, |mut args| {
+                let mut count = 0;
+                loop {
+                    match args.next() {
+                        Arg::Positional(_, _) => count += 1,
+                        Arg::Named(arg) => arg.parse(|_, _| Err(OptionError::Unknown))?,
+                        Arg::End => return Ok(count),
+                        Arg::Error(err) => return Err(err),
+                    }
+                }
+            })
+        };
+        assert_eq!(count("a 'b c' d"), Ok(3));
+        assert_eq!(count(""), Ok(0));
+        match count("a 'b") {
+            Err(LineError::Split(_)) => (),
+            r => panic!("expected split error, got {:?}", r),
+        }
+        let err = count("a --x").unwrap_err();
+        assert_eq!(err.to_string(), "unknown option --x");
+    }
+
+    #[test]
+    fn parse_declared() {
+        let formats = Choices::new(&[("json", 0), ("yaml", 1)]);
+        let session = session(&formats);
+        // Each line shares the session's syntax.
+        let args = session.args("x").unwrap();
+        assert!(Arc::ptr_eq(&args.syntax, &session.syntax));
+        let names = |line| {
+            session.parse(line, |mut args| {
+                let mut names = Vec::new();
+                while let Arg::Named(arg) = args.next() {
+                    arg.parse(|name, _| {
+                        names.push(name.to_owned());
+                        Ok(())
+                    })?;
+                }
+                Ok(names)
+            })
+        };
+        assert_eq!(
+            names("--al --form -a --a --x").unwrap(),
+            ["all", "format", "a", "a", "x"]
+        );
+    }
+}
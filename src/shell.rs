@@ -0,0 +1,445 @@
+//! Shell quoting and splitting, for displaying arguments so they can be pasted back into a shell,
+//! and for reading command lines which are stored as a single string.
+
+use crate::ArgString;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::{self, CharIndices};
+
+/// Return true if a byte does not need quoting anywhere in a word.
+fn is_safe(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b"_-+=%@:,./".contains(&c)
+}
+
+/// Quote an argument for a POSIX shell.
+///
+/// Arguments which only contain safe characters are returned unchanged. Printable text is quoted
+/// with single quotes. Anything else, including control characters and invalid Unicode, is
+/// quoted with `$'...'`, which is supported by Bash, Zsh, and Ksh.
+///
+/// ```
+/// use simpleargs::shell::quote;
+/// assert_eq!(quote(&"file.txt".to_owned()), "file.txt");
+/// assert_eq!(quote(&"my file".to_owned()), "'my file'");
+/// assert_eq!(quote(&"it's\n".to_owned()), r"$'it\'s\n'");
+/// ```
+pub fn quote<A: ArgString>(arg: &A) -> String {
+    quote_bytes(&arg.arg_bytes())
+}
+
+fn quote_bytes(bytes: &[u8]) -> String {
+    if !bytes.is_empty() && bytes.iter().all(|&c| is_safe(c)) {
+        return str::from_utf8(bytes).unwrap().to_owned();
+    }
+    if let Ok(s) = str::from_utf8(bytes) {
+        if !s.chars().any(|c| c == '\'' || c.is_control()) {
+            return format!("'{}'", s);
+        }
+    }
+    let mut out = String::from("$'");
+    let mut rest = bytes;
+    loop {
+        let (valid, invalid) = match str::from_utf8(rest) {
+            Ok(s) => (s, &[][..]),
+            Err(e) => {
+                let (valid, tail) = rest.split_at(e.valid_up_to());
+                let len = e.error_len().unwrap_or(tail.len());
+                rest = &tail[len..];
+                (str::from_utf8(valid).unwrap(), &tail[..len])
+            }
+        };
+        for c in valid.chars() {
+            match c {
+                '\\' => out.push_str(r"\\"),
+                '\'' => out.push_str(r"\'"),
+                '\n' => out.push_str(r"\n"),
+                '\r' => out.push_str(r"\r"),
+                '\t' => out.push_str(r"\t"),
+                c if c.is_control() => {
+                    let mut buf = [0; 4];
+                    for &b in c.encode_utf8(&mut buf).as_bytes() {
+                        out.push_str(&format!(r"\x{:02x}", b));
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        for &b in invalid {
+            out.push_str(&format!(r"\x{:02x}", b));
+        }
+        if invalid.is_empty() {
+            break;
+        }
+    }
+    out.push('\'');
+    out
+}
+
+/// Quote arguments for a POSIX shell and join them with spaces. This is the inverse of [`split`].
+///
+/// The first argument is quoted if it contains `=`, so the shell runs it as a command instead of
+/// reading it as a variable assignment.
+///
+/// ```
+/// use simpleargs::shell::join;
+/// let args = vec!["ls".to_owned(), "My Documents".to_owned()];
+/// assert_eq!(join(&args), "ls 'My Documents'");
+/// let args = vec!["A=b".to_owned(), "A=b".to_owned()];
+/// assert_eq!(join(&args), "'A=b' A=b");
+/// ```
+pub fn join<A: ArgString>(args: &[A]) -> String {
+    let mut out = String::new();
+    for (i, arg) in args.iter().enumerate() {
+        if i != 0 {
+            out.push(' ');
+        }
+        let bytes = arg.arg_bytes();
+        if i == 0 && bytes.contains(&b'=') && bytes.iter().all(|&c| is_safe(c)) {
+            out.push_str(&format!("'{}'", str::from_utf8(&bytes).unwrap()));
+        } else {
+            out.push_str(&quote_bytes(&bytes));
+        }
+    }
+    out
+}
+
+/// An error splitting a command line into arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitError {
+    position: usize,
+    message: &'static str,
+}
+
+impl SplitError {
+    /// Get the byte offset in the command line where the problem starts. For unterminated
+    /// quotes, this is the position of the opening quote.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get a description of the problem, without the position.
+    pub fn message(&self) -> &str {
+        self.message
+    }
+}
+
+impl Display for SplitError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Error for SplitError {}
+
+fn error<T>(position: usize, message: &'static str) -> Result<T, SplitError> {
+    Err(SplitError { position, message })
+}
+
+fn push_char(word: &mut Vec<u8>, c: char) {
+    let mut buf = [0; 4];
+    word.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+/// Get the word being built, starting a new word at the given position if necessary.
+fn start(word: &mut Option<(usize, Vec<u8>)>, pos: usize) -> &mut Vec<u8> {
+    &mut word.get_or_insert_with(|| (pos, Vec::new())).1
+}
+
+/// Split a command line into arguments, like a POSIX shell.
+///
+/// Words are separated by whitespace and may be quoted with single quotes, double quotes,
+/// backslashes, and `$'...'`, which can contain arbitrary bytes like `$'\xff'`. Comments starting
+/// with `#` are ignored. Nothing is expanded: `$HOME`, `*`, and `~` are kept as written.
+/// Operators like `|`, `;`, and `>` are rejected, since they would change the meaning of the
+/// command. This is the inverse of [`join`].
+///
+/// ```
+/// use simpleargs::shell::split;
+/// let args = split(r#"grep -e 'a b' "it's" $'\t'"#).unwrap();
+/// assert_eq!(args, ["grep", "-e", "a b", "it's", "\t"]);
+/// let err = split("echo 'oops").unwrap_err();
+/// assert_eq!(err.to_string(), "unterminated single quote at position 5");
+/// ```
+pub fn split(line: &str) -> Result<Vec<OsString>, SplitError> {
+    use os_str_bytes::OsStringBytes;
+    split_bytes(line)?
+        .into_iter()
+        .map(|(range, word)| {
+            OsString::from_vec(word)
+                .or_else(|_| error(range.start, "argument cannot be represented"))
+        })
+        .collect()
+}
+
+/// An argument from a command line, and the range of the line it was written in.
+pub(crate) type Word = (Range<usize>, Vec<u8>);
+
+/// Split a command line into arguments, as bytes. Returns each argument with the range of the
+/// line it was written in. The last argument ends at the end of the line if nothing follows it.
+pub(crate) fn split_bytes(line: &str) -> Result<Vec<Word>, SplitError> {
+    let mut words = Vec::new();
+    let mut word: Option<(usize, Vec<u8>)> = None;
+    let mut chars = line.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' => words.extend(word.take().map(|(start, w)| (start..pos, w))),
+            '#' if word.is_none() => while chars.next_if(|&(_, c)| c != '\n').is_some() {},
+            '|' | '&' | ';' | '<' | '>' | '(' | ')' | '`' => {
+                return error(pos, "unsupported shell operator")
+            }
+            '\'' => {
+                let word = start(&mut word, pos);
+                loop {
+                    match chars.next() {
+                        None => return error(pos, "unterminated single quote"),
+                        Some((_, '\'')) => break,
+                        Some((_, c)) => push_char(word, c),
+                    }
+                }
+            }
+            '"' => {
+                let word = start(&mut word, pos);
+                loop {
+                    match chars.next() {
+                        None => return error(pos, "unterminated double quote"),
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next_if(|&(_, c)| "$`\"\\\n".contains(c)) {
+                            Some((_, '\n')) => (),
+                            Some((_, c)) => push_char(word, c),
+                            None => word.push(b'\\'),
+                        },
+                        Some((_, c)) => push_char(word, c),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                None => return error(pos, "backslash at end of line"),
+                Some((_, '\n')) => (),
+                Some((_, c)) => push_char(start(&mut word, pos), c),
+            },
+            '$' if chars.next_if(|&(_, c)| c == '\'').is_some() => {
+                split_ansi_c(&mut chars, pos, start(&mut word, pos))?;
+            }
+            c => push_char(start(&mut word, pos), c),
+        }
+    }
+    words.extend(word.map(|(start, w)| (start..line.len(), w)));
+    Ok(words)
+}
+
+/// Parse the rest of a `$'...'` string, which starts at the given position.
+fn split_ansi_c(
+    chars: &mut Peekable<CharIndices>,
+    start: usize,
+    word: &mut Vec<u8>,
+) -> Result<(), SplitError> {
+    loop {
+        let (pos, c) = match chars.next() {
+            None => return error(start, "unterminated $' quote"),
+            Some(x) => x,
+        };
+        match c {
+            '\'' => return Ok(()),
+            '\\' => (),
+            c => {
+                push_char(word, c);
+                continue;
+            }
+        }
+        let c = match chars.next() {
+            None => return error(start, "unterminated $' quote"),
+            Some((_, c)) => c,
+        };
+        let byte = match c {
+            'a' => 0x07,
+            'b' => 0x08,
+            'e' | 'E' => 0x1b,
+            'f' => 0x0c,
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            'v' => 0x0b,
+            '\\' | '\'' | '"' | '?' => c as u8,
+            'x' => match digits(chars, 16, 2, 0) {
+                (_, 0) => return error(pos, "missing hex digits in escape"),
+                (n, _) => n as u8,
+            },
+            // Values above 0o377 wrap around, as in Bash.
+            '0'..='7' => digits(chars, 8, 2, c.to_digit(8).unwrap()).0 as u8,
+            'u' | 'U' => {
+                let c = match digits(chars, 16, if c == 'u' { 4 } else { 8 }, 0) {
+                    (_, 0) => None,
+                    (n, _) => std::char::from_u32(n),
+                };
+                match c {
+                    Some(c) => {
+                        push_char(word, c);
+                        continue;
+                    }
+                    None => return error(pos, "invalid Unicode escape"),
+                }
+            }
+            'c' => match chars.next() {
+                Some((_, c)) if c.is_ascii() => c as u8 & 0x1f,
+                _ => return error(pos, "invalid control character escape"),
+            },
+            _ => return error(pos, "unknown escape sequence"),
+        };
+        word.push(byte);
+    }
+}
+
+/// Read up to `max` more digits in the given radix, appending them to `value`. Returns the value
+/// and the number of digits read.
+fn digits(
+    chars: &mut Peekable<CharIndices>,
+    radix: u32,
+    max: usize,
+    mut value: u32,
+) -> (u32, usize) {
+    let mut count = 0;
+    while count < max {
+        match chars.next_if(|&(_, c)| c.is_digit(radix)) {
+            Some((_, c)) => value = value * radix + c.to_digit(radix).unwrap(),
+            None => break,
+        }
+        count += 1;
+    }
+    (value, count)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ffi::OsString;
+
+    #[test]
+    fn quoting() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"abc", "abc"),
+            (b"--out=a/b.txt", "--out=a/b.txt"),
+            (b"", "''"),
+            (b"a b", "'a b'"),
+            (b"$HOME", "'$HOME'"),
+            ("caf\u{e9}".as_bytes(), "'caf\u{e9}'"),
+            (b"'", r"$'\''"),
+            (b"a\\b\t", r"$'a\\b\t'"),
+            (b"\x1b[m", r"$'\x1b[m'"),
+            (b"\xff", r"$'\xff'"),
+            (b"a\xffb\xe9", r"$'a\xffb\xe9'"),
+            ("\u{85}".as_bytes(), r"$'\xc2\x85'"),
+        ];
+        for &(input, expect) in cases.iter() {
+            assert_eq!(quote_bytes(input), expect, "input: {:?}", input);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quote_osstring() {
+        use std::os::unix::ffi::OsStringExt;
+        assert_eq!(quote(&OsString::from_vec(b"x\xff".to_vec())), r"$'x\xff'");
+    }
+
+    fn split_strs(line: &str) -> Result<Vec<String>, (usize, &'static str)> {
+        match split_bytes(line) {
+            Ok(words) => Ok(words
+                .into_iter()
+                .map(|(_, word)| String::from_utf8(word).unwrap())
+                .collect()),
+            Err(e) => Err((e.position(), e.message)),
+        }
+    }
+
+    #[test]
+    fn split_words() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("  a\tb\nc  ", &["a", "b", "c"]),
+            ("a'b c'd", &["ab cd"]),
+            ("'' \"\"", &["", ""]),
+            (r#""a\"b\$c\d\\""#, &[r#"a"b$c\d\"#]),
+            ("a\\ b \\'c", &["a b", "'c"]),
+            ("a\\\nb", &["ab"]),
+            ("\"a\\\nb\"", &["ab"]),
+            ("a # comment\nb#c", &["a", "b#c"]),
+            ("$HOME * ~ $", &["$HOME", "*", "~", "$"]),
+            (
+                r#"$'\a\b\e\f\n\r\t\v\\\'\"\?'"#,
+                &["\x07\x08\x1b\x0c\n\r\t\x0b\\'\"?"],
+            ),
+            (r"$'\x41\x4a2\101\0'", &["AJ2A\0"]),
+            (r"$'é\U0001F600\cA'", &["\u{e9}\u{1f600}\x01"]),
+            (r"x$'y'z", &["xyz"]),
+        ];
+        for &(input, expect) in cases.iter() {
+            assert_eq!(
+                split_strs(input),
+                Ok(expect.iter().map(|&s| s.to_owned()).collect())
+            );
+        }
+    }
+
+    #[test]
+    fn split_ranges() {
+        let ranges = |line| -> Vec<(usize, usize)> {
+            split_bytes(line)
+                .unwrap()
+                .into_iter()
+                .map(|(range, _)| (range.start, range.end))
+                .collect()
+        };
+        assert_eq!(ranges("a  'b c' "), [(0, 1), (3, 8)]);
+        assert_eq!(ranges(r"a\ "), [(0, 3)]);
+        assert_eq!(ranges("x # y"), [(0, 1)]);
+    }
+
+    #[test]
+    fn split_errors() {
+        let cases: &[(&str, usize, &str)] = &[
+            ("a 'b", 2, "unterminated single quote"),
+            ("a \"b", 2, "unterminated double quote"),
+            ("a $'b", 2, "unterminated $' quote"),
+            ("a $'b\\", 2, "unterminated $' quote"),
+            ("ab\\", 2, "backslash at end of line"),
+            ("a | b", 2, "unsupported shell operator"),
+            ("a;", 1, "unsupported shell operator"),
+            ("\u{e9} >x", 3, "unsupported shell operator"),
+            (r"$'\xg'", 2, "missing hex digits in escape"),
+            (r"$'\ud800'", 2, "invalid Unicode escape"),
+            (r"$'\q'", 2, "unknown escape sequence"),
+        ];
+        for &(input, position, message) in cases.iter() {
+            assert_eq!(
+                split_strs(input),
+                Err((position, message)),
+                "input: {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let args: Vec<OsString> = [
+            "", "a", "a b", "'", "\"", "\\", "$x", "#", "~", "\n", "\u{e9}", "\u{85}", "a'b\"c",
+        ]
+        .iter()
+        .map(OsString::from)
+        .collect();
+        assert_eq!(split(&join(&args)).unwrap(), args);
+        // A first word with "=" would be a variable assignment.
+        let args = vec![OsString::from("A=b"), OsString::from("cmd")];
+        assert_eq!(join(&args), "'A=b' cmd");
+        assert_eq!(split(&join(&args)).unwrap(), args);
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+            let args = vec![OsString::from_vec(b"\xff\x80 x".to_vec())];
+            assert_eq!(split(&join(&args)).unwrap(), args);
+        }
+    }
+}
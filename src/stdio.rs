@@ -0,0 +1,230 @@
+//! Files which may be standard input or output.
+
+use crate::ArgString;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The argument which a path came from, for error messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Origin {
+    /// A named option, as the user typed it, like "--name".
+    Option(String),
+    /// A positional argument, with the name used in usage messages.
+    Argument(String),
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Origin::Option(option) => write!(f, "option {}", option),
+            Origin::Argument(name) => write!(f, "argument <{}>", name),
+        }
+    }
+}
+
+/// Convert an argument to a path, or None for `-`.
+fn to_path(arg: &OsStr) -> Option<PathBuf> {
+    if arg == "-" {
+        None
+    } else {
+        Some(PathBuf::from(arg))
+    }
+}
+
+/// An input file from the command line, which is standard input if the argument is `-`.
+///
+/// The file is not opened until [`open`] is called. Errors from opening the file say which option
+/// or argument the path came from.
+///
+/// [`open`]: Input::open
+///
+/// ```
+/// use simpleargs::Input;
+/// let input = Input::from_arg(&"-".to_owned(), "input");
+/// assert!(input.is_stdin());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    path: Option<PathBuf>,
+    origin: Origin,
+}
+
+impl Input {
+    /// Create an input from a positional argument. The name is used in error messages.
+    pub fn from_arg<A: ArgString>(arg: &A, name: &str) -> Self {
+        Input {
+            path: to_path(arg.to_osstr()),
+            origin: Origin::Argument(name.to_owned()),
+        }
+    }
+
+    /// Create an input from the value of a named option. The option is used in error messages, and
+    /// should be spelled the way the user typed it, like `--name`, as returned by
+    /// [`NamedArgument::spelling`].
+    ///
+    /// [`NamedArgument::spelling`]: crate::NamedArgument::spelling
+    pub fn from_option(value: &OsStr, option: &str) -> Self {
+        Input {
+            path: to_path(value),
+            origin: Origin::Option(option.to_owned()),
+        }
+    }
+
+    /// Return true if this is standard input.
+    pub fn is_stdin(&self) -> bool {
+        self.path.is_none()
+    }
+
+    /// Get the path to the file, or None for standard input.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Open the file for reading, or return standard input.
+    pub fn open(&self) -> Result<Box<dyn Read>, OpenError> {
+        match &self.path {
+            None => Ok(Box::new(io::stdin())),
+            Some(path) => match File::open(path) {
+                Ok(file) => Ok(Box::new(file)),
+                Err(err) => Err(OpenError {
+                    path: path.clone(),
+                    origin: self.origin.clone(),
+                    err,
+                }),
+            },
+        }
+    }
+}
+
+/// An output file from the command line, which is standard output if the argument is `-`.
+///
+/// The file is not created until [`create`] is called. Errors from creating the file say which
+/// option or argument the path came from.
+///
+/// [`create`]: Output::create
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    path: Option<PathBuf>,
+    origin: Origin,
+}
+
+impl Output {
+    /// Create an output from a positional argument. The name is used in error messages.
+    pub fn from_arg<A: ArgString>(arg: &A, name: &str) -> Self {
+        Output {
+            path: to_path(arg.to_osstr()),
+            origin: Origin::Argument(name.to_owned()),
+        }
+    }
+
+    /// Create an output from the value of a named option. The option is used in error messages, and
+    /// should be spelled the way the user typed it, like `--name`, as returned by
+    /// [`NamedArgument::spelling`].
+    ///
+    /// [`NamedArgument::spelling`]: crate::NamedArgument::spelling
+    pub fn from_option(value: &OsStr, option: &str) -> Self {
+        Output {
+            path: to_path(value),
+            origin: Origin::Option(option.to_owned()),
+        }
+    }
+
+    /// Return true if this is standard output.
+    pub fn is_stdout(&self) -> bool {
+        self.path.is_none()
+    }
+
+    /// Get the path to the file, or None for standard output.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Create or truncate the file for writing, or return standard output.
+    pub fn create(&self) -> Result<Box<dyn Write>, OpenError> {
+        match &self.path {
+            None => Ok(Box::new(io::stdout())),
+            Some(path) => match File::create(path) {
+                Ok(file) => Ok(Box::new(file)),
+                Err(err) => Err(OpenError {
+                    path: path.clone(),
+                    origin: self.origin.clone(),
+                    err,
+                }),
+            },
+        }
+    }
+}
+
+/// An error opening a file named on the command line.
+#[derive(Debug)]
+pub struct OpenError {
+    path: PathBuf,
+    origin: Origin,
+    err: io::Error,
+}
+
+impl OpenError {
+    /// Get the path to the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the underlying I/O error.
+    pub fn io_error(&self) -> &io::Error {
+        &self.err
+    }
+}
+
+impl Display for OpenError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "could not open {:?} for {}: {}",
+            self.path, self.origin, self.err
+        )
+    }
+}
+
+impl Error for OpenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stdio() {
+        assert!(Input::from_arg(&"-".to_owned(), "input").is_stdin());
+        assert!(Output::from_option(OsStr::new("-"), "--output").is_stdout());
+        let input = Input::from_arg(&"./-".to_owned(), "input");
+        assert_eq!(input.path(), Some(Path::new("./-")));
+    }
+
+    #[test]
+    fn open_error() {
+        let path = "/nonexistent/simpleargs-test";
+        match Input::from_option(OsStr::new(path), "--input").open() {
+            Ok(_) => panic!("open succeeded"),
+            Err(err) => {
+                let msg = err.to_string();
+                let expect = format!("could not open {:?} for option --input: ", path);
+                assert!(msg.starts_with(&expect), "message: {}", msg);
+            }
+        }
+        match Output::from_arg(&path.to_owned(), "output").create() {
+            Ok(_) => panic!("create succeeded"),
+            Err(err) => {
+                let msg = err.to_string();
+                let expect = format!("could not open {:?} for argument <output>: ", path);
+                assert!(msg.starts_with(&expect), "message: {}", msg);
+            }
+        }
+    }
+}
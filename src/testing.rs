@@ -0,0 +1,223 @@
+//! Helpers for testing argument parsers.
+//!
+//! Command lines are written as a single string, split the way a POSIX shell would split them,
+//! so invalid Unicode can be written with `$'\xff'`. Errors are checked by kind, using the
+//! identifiers from [`Message::id`], and values are compared quoted with [`shell::quote`].
+//!
+//! ```
+//! use simpleargs::testing::{assert_error, error_text, run};
+//! use simpleargs::{Arg, Args, OptionError, UsageError};
+//! use std::ffi::OsString;
+//!
+//! fn parse<T>(mut args: Args<T>) -> Result<i32, UsageError<T::Item>>
+//! where
+//!     T: Iterator,
+//!     T::Item: simpleargs::ArgString,
+//! {
+//!     let mut count = 0;
+//!     loop {
+//!         match args.next() {
+//!             Arg::Named(arg) => arg.parse(|name, value| match name {
+//!                 "count" => {
+//!                     count = value.as_str()?.parse()?;
+//!                     Ok(())
+//!                 }
+//!                 _ => Err(OptionError::Unknown),
+//!             })?,
+//!             Arg::Positional(arg, _) => return Err(UsageError::UnexpectedArgument { arg }),
+//!             Arg::End => return Ok(count),
+//!             Arg::Error(err) => return Err(err),
+//!         }
+//!     }
+//! }
+//!
+//! assert_eq!(run::<String, _, _>("--count 3", parse).unwrap(), 3);
+//! let r = run::<OsString, _, _>(r"--count=$'\xff'", parse);
+//! assert_error(&r, "invalid-unicode", Some("--count"), Some(r"$'\xff'"));
+//! assert_eq!(
+//!     error_text(&r),
+//!     r"invalid value $'\xff' for option --count: invalid Unicode string",
+//! );
+//! ```
+//!
+//! [`Message::id`]: crate::Message::id
+
+use crate::shell::{self, split_bytes};
+use crate::{ArgString, Args, UsageError};
+use std::ffi::OsString;
+use std::fmt::Debug;
+use std::vec;
+
+/// An argument type which can be created from test input.
+pub trait TestArg: ArgString + Debug {
+    /// Create an argument from bytes. Panics if the bytes cannot be represented.
+    fn from_test_bytes(bytes: Vec<u8>) -> Self;
+}
+
+impl TestArg for String {
+    fn from_test_bytes(bytes: Vec<u8>) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => panic!("test argument is not valid UTF-8: {:?}", e.as_bytes()),
+        }
+    }
+}
+
+impl TestArg for OsString {
+    fn from_test_bytes(bytes: Vec<u8>) -> Self {
+        use os_str_bytes::OsStringBytes;
+        match OsString::from_vec(bytes) {
+            Ok(s) => s,
+            Err(e) => panic!("test argument cannot be represented: {}", e),
+        }
+    }
+}
+
+/// Split a command line into arguments, like a POSIX shell. Panics if the command line is
+/// invalid.
+pub fn split<A: TestArg>(line: &str) -> Vec<A> {
+    match split_bytes(line) {
+        Ok(words) => words
+            .into_iter()
+            .map(|(_, word)| A::from_test_bytes(word))
+            .collect(),
+        Err(e) => panic!("invalid test command line {:?}: {}", line, e),
+    }
+}
+
+/// Create an argument stream from a command line. The program name should not be included.
+pub fn args<A: TestArg>(line: &str) -> Args<vec::IntoIter<A>> {
+    Args::from(split(line).into_iter())
+}
+
+/// Run a parsing function over a command line.
+pub fn run<A, U, F>(line: &str, f: F) -> Result<U, UsageError<A>>
+where
+    A: TestArg,
+    F: FnOnce(Args<vec::IntoIter<A>>) -> Result<U, UsageError<A>>,
+{
+    f(args(line))
+}
+
+/// Get the error from a result. Panics if the result is not an error.
+fn unwrap_err<U, A>(result: &Result<U, UsageError<A>>) -> &UsageError<A>
+where
+    U: Debug,
+{
+    match result {
+        Ok(value) => panic!("expected an error, got {:?}", value),
+        Err(err) => err,
+    }
+}
+
+/// Assert that a result is an error of the given kind, for the given option, with the given
+/// value.
+///
+/// The kind is an identifier from [`Message::id`], or `"multiple"` for
+/// [`UsageError::Multiple`]. The option is the option as the user typed it, like `--name`, and
+/// is only present for errors from [`NamedArgument::parse`]. The value is the argument or option
+/// value, quoted with [`shell::quote`].
+///
+/// [`Message::id`]: crate::Message::id
+/// [`NamedArgument::parse`]: crate::NamedArgument::parse
+pub fn assert_error<U, A>(
+    result: &Result<U, UsageError<A>>,
+    kind: &str,
+    option: Option<&str>,
+    value: Option<&str>,
+) where
+    U: Debug,
+    A: ArgString + Debug,
+{
+    let err = unwrap_err(result);
+    let actual_kind = match err.message() {
+        Some(message) => message.id(),
+        None => "multiple",
+    };
+    let actual_option = match err {
+        UsageError::InvalidOption { option, .. } => Some(option.as_str()),
+        _ => None,
+    };
+    let actual_value = err.value().map(shell::quote);
+    assert_eq!(
+        (actual_kind, actual_option, actual_value.as_deref()),
+        (kind, option, value),
+        "error: {}",
+        err.quoted(),
+    );
+}
+
+/// Get the text of the error from a result, with values quoted for the shell. Panics if the
+/// result is not an error.
+pub fn error_text<U, A>(result: &Result<U, UsageError<A>>) -> String
+where
+    U: Debug,
+    A: ArgString + Debug,
+{
+    unwrap_err(result).quoted().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Arg, OptionError};
+
+    fn parse<A: TestArg>(mut args: Args<vec::IntoIter<A>>) -> Result<Vec<A>, UsageError<A>> {
+        let mut result = Vec::new();
+        loop {
+            match args.next() {
+                Arg::Positional(arg, _) => result.push(arg),
+                Arg::Named(arg) => arg.parse(|name, value| match name {
+                    "n" => value.as_str()?.parse::<i32>().map(drop).map_err(Into::into),
+                    _ => Err(OptionError::Unknown),
+                })?,
+                Arg::End => return Ok(result),
+                Arg::Error(err) => return Err(err),
+            }
+        }
+    }
+
+    fn check<A: TestArg + PartialEq>() {
+        let r = run::<A, _, _>(r#"a 'b c' "d\"e" -n 1 $'f\tg'"#, parse).unwrap();
+        let expect: Vec<A> = ["a", "b c", "d\"e", "f\tg"]
+            .iter()
+            .map(|s| A::from_test_bytes(s.as_bytes().to_vec()))
+            .collect();
+        assert_eq!(r, expect);
+        let r = run::<A, _, _>("-n 'two words'", parse);
+        assert_error(&r, "invalid-value", Some("-n"), Some("'two words'"));
+        assert_eq!(
+            error_text(&r),
+            "invalid value 'two words' for option -n: invalid digit found in string"
+        );
+        let r = run::<A, _, _>("--x", parse);
+        assert_error(&r, "unknown-option", Some("--x"), None);
+    }
+
+    #[test]
+    fn string() {
+        check::<String>();
+    }
+
+    #[test]
+    fn osstring() {
+        check::<OsString>();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn invalid_unicode() {
+        let r = run::<OsString, _, _>(r"-n $'\xff'", parse);
+        assert_error(&r, "invalid-unicode", Some("-n"), Some(r"$'\xff'"));
+        assert_eq!(
+            split::<OsString>(r"$'\xff'")[0].arg_bytes().as_ref(),
+            b"\xff"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "not valid UTF-8")]
+    fn invalid_string() {
+        split::<String>(r"$'\xff'");
+    }
+}
@@ -0,0 +1,238 @@
+//! Windows command line splitting and quoting, following the rules used by `CommandLineToArgvW`.
+//! This works on any platform.
+//!
+//! The Microsoft C runtime splits command lines for `main` with almost the same rules. Since
+//! 2008, it differs in one case: two double quotes in a row inside quotes make a literal double
+//! quote and stay inside the quotes, while `CommandLineToArgvW` ends the quotes after the literal
+//! double quote. So `a"b"" c` is one argument `ab" c` for the C runtime, but two arguments `ab"`
+//! and `c` here. Arguments quoted with [`quote`] are split the same way by both.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Split a Windows command line into arguments, like `CommandLineToArgvW`.
+///
+/// The first argument is the program name, which is parsed by different rules: it ends at the
+/// first space or tab outside of quotes, and backslashes are taken literally. A command line
+/// which starts with whitespace has an empty program name. In the remaining arguments:
+///
+/// - Arguments are separated by spaces and tabs, outside of double quotes.
+/// - Backslashes are literal, unless they are followed by a double quote. Then each pair of
+///   backslashes becomes one backslash, and an odd backslash escapes the quote.
+/// - Inside double quotes, two double quotes in a row make one literal double quote, and end the
+///   quoted part.
+///
+/// ```
+/// use simpleargs::Args;
+/// use simpleargs::windows::split;
+/// let mut args = split(r#"C:\bin\tool.exe -o "C:\Program Files\out.txt" a\\"b"#);
+/// assert_eq!(args.next().unwrap(), r"C:\bin\tool.exe");
+/// let args = Args::from(args);
+/// # let rest: Vec<String> = args.rest().collect();
+/// # assert_eq!(rest, ["-o", r"C:\Program Files\out.txt", r"a\b"]);
+/// ```
+pub fn split(line: &str) -> Split<'_> {
+    Split {
+        chars: line.chars().peekable(),
+        program: true,
+    }
+}
+
+/// An iterator over the arguments in a Windows command line. Returned by [`split`].
+#[derive(Debug, Clone)]
+pub struct Split<'a> {
+    chars: Peekable<Chars<'a>>,
+    program: bool,
+}
+
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn push_backslashes(out: &mut String, count: usize) {
+    for _ in 0..count {
+        out.push('\\');
+    }
+}
+
+impl<'a> Split<'a> {
+    /// Parse the program name, which has no escapes.
+    fn program(&mut self) -> Option<String> {
+        self.chars.peek()?;
+        let mut arg = String::new();
+        let mut in_quotes = false;
+        for c in &mut self.chars {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                c if is_space(c) && !in_quotes => break,
+                c => arg.push(c),
+            }
+        }
+        Some(arg)
+    }
+
+    /// Parse an argument after the program name.
+    fn argument(&mut self) -> Option<String> {
+        while self.chars.next_if(|&c| is_space(c)).is_some() {}
+        self.chars.peek()?;
+        let mut arg = String::new();
+        let mut in_quotes = false;
+        while let Some(c) = self.chars.next() {
+            match c {
+                c if is_space(c) && !in_quotes => break,
+                '\\' => {
+                    let mut count = 1;
+                    while self.chars.next_if_eq(&'\\').is_some() {
+                        count += 1;
+                    }
+                    if self.chars.peek() == Some(&'"') {
+                        push_backslashes(&mut arg, count / 2);
+                        if count % 2 == 1 {
+                            self.chars.next();
+                            arg.push('"');
+                        }
+                    } else {
+                        push_backslashes(&mut arg, count);
+                    }
+                }
+                '"' if in_quotes => {
+                    if self.chars.next_if_eq(&'"').is_some() {
+                        arg.push('"');
+                    }
+                    in_quotes = false;
+                }
+                '"' => in_quotes = true,
+                c => arg.push(c),
+            }
+        }
+        Some(arg)
+    }
+}
+
+impl<'a> Iterator for Split<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.program {
+            self.program = false;
+            self.program()
+        } else {
+            self.argument()
+        }
+    }
+}
+
+/// Quote an argument so it is parsed as a single argument by [`split`]. Arguments without spaces,
+/// tabs, or double quotes are returned unchanged.
+///
+/// This should not be used for the program name, which cannot contain escapes.
+///
+/// ```
+/// use simpleargs::windows::quote;
+/// assert_eq!(quote(r"C:\dir"), r"C:\dir");
+/// assert_eq!(quote(r"C:\My Files\"), r#""C:\My Files\\""#);
+/// assert_eq!(quote(r#"say "hi""#), r#""say \"hi\"""#);
+/// ```
+pub fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c| is_space(c) || c == '\n' || c == '\x0b' || c == '"') {
+        return arg.to_owned();
+    }
+    let mut out = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                push_backslashes(&mut out, backslashes * 2 + 1);
+                out.push('"');
+                backslashes = 0;
+            }
+            c => {
+                push_backslashes(&mut out, backslashes);
+                out.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // Backslashes before the closing quote must be escaped.
+    push_backslashes(&mut out, backslashes * 2);
+    out.push('"');
+    out
+}
+
+/// Join a program name and arguments into a Windows command line. This is the inverse of
+/// [`split`].
+///
+/// The program name is quoted if it contains spaces or tabs. Panics if the program name contains
+/// a double quote, since that cannot be represented.
+///
+/// ```
+/// use simpleargs::windows::join;
+/// let line = join(r"C:\Program Files\tool.exe", &["a b", "c"]);
+/// assert_eq!(line, r#""C:\Program Files\tool.exe" "a b" c"#);
+/// ```
+pub fn join<S: AsRef<str>>(program: &str, args: &[S]) -> String {
+    assert!(
+        !program.contains('"'),
+        "program name contains a double quote"
+    );
+    let mut out = if program.is_empty() || program.contains(is_space) {
+        format!("\"{}\"", program)
+    } else {
+        program.to_owned()
+    };
+    for arg in args.iter() {
+        out.push(' ');
+        out.push_str(&quote(arg.as_ref()));
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_args() {
+        // Examples from the Microsoft C runtime documentation, and known edge cases.
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("prog", &["prog"]),
+            (" a b", &["", "a", "b"]),
+            ("prog  \t ", &["prog"]),
+            (
+                r#""C:\Program Files\a.exe" x"#,
+                &[r"C:\Program Files\a.exe", "x"],
+            ),
+            (r#"C:\"x y"\z\ a"#, &[r"C:\x y\z\", "a"]),
+            (r#"prog "abc" d e"#, &["prog", "abc", "d", "e"]),
+            (r#"prog a\\b d"e f"g h"#, &["prog", r"a\\b", "de fg", "h"]),
+            (r#"prog a\\\"b c d"#, &["prog", r#"a\"b"#, "c", "d"]),
+            (r#"prog a\\\\"b c" d e"#, &["prog", r"a\\b c", "d", "e"]),
+            (r#"prog a"b"" c d"#, &["prog", r#"ab""#, "c", "d"]),
+            (r#"prog "a""b" c"#, &["prog", r#"a"b c"#]),
+            (r#"prog "" """#, &["prog", "", ""]),
+            (r#"prog """""#, &["prog", r#"""#]),
+            (r#"prog "a"#, &["prog", "a"]),
+            (r#"prog a\"#, &["prog", r"a\"]),
+            ("prog a\tb\nc", &["prog", "a", "b\nc"]),
+        ];
+        for &(input, expect) in cases.iter() {
+            let args: Vec<String> = split(input).collect();
+            assert_eq!(args, expect, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let args = [
+            "", "a", "a b", "\t", "\"", "\\", "\\\\", "a\\", "a\\\"", "\\\" \\", "\"\"", "x\ny",
+        ];
+        let line = join("prog", &args);
+        let result: Vec<String> = split(&line).collect();
+        assert_eq!(result[0], "prog");
+        assert_eq!(result[1..], args);
+        let result: Vec<String> = split(&join("my prog", &["x"])).collect();
+        assert_eq!(result, ["my prog", "x"]);
+    }
+}